@@ -0,0 +1,192 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Type};
+
+/// implement `crossbytes::overlay::Overlay` for a `#[repr(C)]` struct with named fields.
+/// the struct must have no padding, be aligned to at most `MAX_OVERLAY_ALIGN` and all fields
+/// must be `Pod`, this is checked at compile time.
+/// generates `<Name>Overlay<'a>`, the accessor returned by `BytesAtomicView::overlay`, with
+/// `load`/`store` for the whole struct, `field()` returning `&AtomicXxx` for atomic fields and
+/// `field()`/`set_field(val)` copying the other fields.
+///
+/// overlays are copied in and out as bytes, so types with drop glue are rejected
+/// ```compile_fail
+/// #[derive(crossbytes_derive::Overlay)]
+/// #[repr(C)]
+/// struct Handle {
+///     fd: u64,
+/// }
+///
+/// impl Drop for Handle {
+///     fn drop(&mut self) {}
+/// }
+/// ```
+/// as are fields which are not `Pod`
+/// ```compile_fail
+/// #[derive(crossbytes_derive::Overlay)]
+/// #[repr(C)]
+/// struct Named {
+///     name: String,
+/// }
+/// ```
+#[proc_macro_derive(Overlay)]
+pub fn derive_overlay(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let vis = &input.vis;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "Overlay can not be derived for generic structs",
+        ));
+    }
+    check_repr(name, &input.attrs)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "Overlay needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "Overlay can only be derived for structs",
+            ))
+        }
+    };
+
+    let accessor = format_ident!("{}Overlay", name);
+    let field_types: Vec<&Type> = fields.iter().map(|field| &field.ty).collect();
+    let mut type_checks = Vec::new();
+    let mut methods = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        if is_atomic(ty) {
+            type_checks.push(quote! { atomic_field::<#ty>(); });
+            methods.push(quote! {
+                pub fn #ident(&self) -> &'a #ty {
+                    self.view.atomic(::core::mem::offset_of!(#name, #ident))
+                }
+            });
+        } else {
+            let setter = format_ident!("set_{}", ident);
+            type_checks.push(quote! { pod::<#ty>(); });
+            methods.push(quote! {
+                pub fn #ident(&self) -> #ty {
+                    self.view.read(::core::mem::offset_of!(#name, #ident))
+                }
+
+                pub fn #setter(&mut self, val: #ty) {
+                    self.view.write(::core::mem::offset_of!(#name, #ident), val)
+                }
+            });
+        }
+    }
+
+    let padding_msg = format!("{} has padding, it can not be an Overlay", name);
+    let align_msg = format!("{} is aligned to more than MAX_OVERLAY_ALIGN", name);
+    let drop_msg = format!("{} implements Drop, it can not be an Overlay", name);
+    let accessor_doc = format!("typed accessor of a `{}` laid over a view", name);
+    Ok(quote! {
+        const _: () = {
+            assert!(
+                ::core::mem::size_of::<#name>() == 0 #(+ ::core::mem::size_of::<#field_types>())*,
+                #padding_msg
+            );
+            assert!(
+                ::core::mem::align_of::<#name>() <= ::crossbytes::overlay::MAX_OVERLAY_ALIGN,
+                #align_msg
+            );
+            assert!(!::core::mem::needs_drop::<#name>(), #drop_msg);
+        };
+
+        const _: fn() = || {
+            fn pod<T: ::crossbytes::overlay::Pod>() {}
+            fn atomic_field<T: ::crossbytes::overlay::AtomicField>() {}
+            #(#type_checks)*
+        };
+
+        unsafe impl ::crossbytes::overlay::Pod for #name {}
+
+        unsafe impl ::crossbytes::overlay::Overlay for #name {
+            type Accessor<'a> = #accessor<'a>;
+
+            fn accessor(
+                view: ::crossbytes::overlay::OverlayView<'_, Self>,
+            ) -> Self::Accessor<'_> {
+                #accessor { view }
+            }
+        }
+
+        #[doc = #accessor_doc]
+        #vis struct #accessor<'a> {
+            view: ::crossbytes::overlay::OverlayView<'a, #name>,
+        }
+
+        #[allow(dead_code)]
+        impl<'a> #accessor<'a> {
+            /// copy of the whole struct
+            pub fn load(&self) -> #name {
+                self.view.load()
+            }
+
+            /// overwrite the whole struct
+            pub fn store(&mut self, val: #name) {
+                self.view.store(val)
+            }
+
+            #(#methods)*
+        }
+    })
+}
+
+// repr(C) or repr(transparent) is required for a stable layout, packed would misalign fields
+fn check_repr(name: &syn::Ident, attrs: &[Attribute]) -> Result<(), Error> {
+    let mut stable = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") || meta.path.is_ident("transparent") {
+                stable = true;
+            } else if meta.path.is_ident("packed") {
+                return Err(meta.error("packed structs can not be an Overlay"));
+            }
+            //skip the arguments of eg align(8)
+            if meta.input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<TokenStream2>()?;
+            }
+            Ok(())
+        })?;
+    }
+    if stable {
+        Ok(())
+    } else {
+        Err(Error::new_spanned(name, "Overlay needs #[repr(C)]"))
+    }
+}
+
+// atomics are recognised by name, AtomicField checks the guess at compile time
+fn is_atomic(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident.to_string().starts_with("Atomic")),
+        _ => false,
+    }
+}
@@ -1,9 +1,14 @@
 use crate::broadcast::RxErr::Overwritten;
-use crate::bytes::{AtomicRefCell, BytesAtomicView, LoadStore};
+use crate::bytes::{
+    AtomicLoad, AtomicRefCell, Bytes, BytesAtomicView, BytesError, BytesReadView, ExclusiveView,
+    LoadStore, OwnedAtomicView, OwnedReadView,
+};
+use crate::header::{RegionKind, SegmentHeader, HEADER_LENGTH};
 use std::ops::BitAnd;
 use std::sync::atomic;
 use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 const TRAILER_SIZE: usize = 128;
 const TAIL_INTENT_COUNTER_OFFSET: u32 = 0;
@@ -23,7 +28,7 @@ impl MsgTypeId {
 }
 
 struct CountersInner<'a> {
-    buff: BytesAtomicView<'a>,
+    buff: ExclusiveView<'a>,
 }
 
 impl<'a> CountersInner<'a> {
@@ -66,9 +71,10 @@ impl<'a> CountersInner<'a> {
         self.latest_record_counter()
             .store(latest_value_counter, Release);
         self.tail_counter().store(tail_counter, Release);
+        #[cfg(debug_assertions)]
         self.check_invariants();
     }
-    fn new(buffer: BytesAtomicView<'a>) -> CountersInner<'a> {
+    fn new(buffer: ExclusiveView<'a>) -> CountersInner<'a> {
         let length = buffer.len();
         assert_eq!(length, TRAILER_SIZE);
 
@@ -76,10 +82,40 @@ impl<'a> CountersInner<'a> {
     }
 }
 
+// read only access to the counters for receivers
+struct RxCounters<'a> {
+    buff: BytesReadView<'a>,
+}
+
+impl<'a> RxCounters<'a> {
+    #[inline]
+    fn latest_record_counter(&self, ordering: Ordering) -> u64 {
+        self.buff.load_at(LAST_COUNTER_OFFSET as usize, ordering)
+    }
+
+    #[inline]
+    fn tail_counter(&self, ordering: Ordering) -> u64 {
+        self.buff.load_at(TAIL_COUNTER_OFFSET as usize, ordering)
+    }
+
+    #[inline]
+    fn tail_intent_counter(&self, ordering: Ordering) -> u64 {
+        self.buff
+            .load_at(TAIL_INTENT_COUNTER_OFFSET as usize, ordering)
+    }
+
+    fn new(buffer: BytesReadView<'a>) -> RxCounters<'a> {
+        let length = buffer.len();
+        assert_eq!(length, TRAILER_SIZE);
+
+        RxCounters { buff: buffer }
+    }
+}
+
 #[cfg(target_has_atomic = "64")]
 pub struct BroadcastTx<'a> {
     counters_inner: CountersInner<'a>,
-    buffer: BytesAtomicView<'a>,
+    buffer: ExclusiveView<'a>,
 }
 
 impl<'a> BroadcastTx<'a> {
@@ -90,13 +126,24 @@ impl<'a> BroadcastTx<'a> {
             (capacity).is_power_of_two(),
             "invalid buffer size, not pow of 2 + TrailerLength"
         );
-        let index_inner = CountersInner::new(buffer.sub_slice(capacity..));
+        //the data area and the trailer are split apart so neither is accessed through the other
+        let (buffer, trailer) = buffer.split_at(capacity as usize);
         BroadcastTx {
-            counters_inner: index_inner,
-            buffer: buffer.sub_view(0, capacity),
+            counters_inner: CountersInner::new(trailer),
+            buffer,
         }
     }
 
+    /// lay out a broadcast buffer after a `SegmentHeader` at the start of bytes and mark
+    /// the segment ready, receivers attach to it with `BroadcastRx::attach`
+    pub fn create(bytes: &'a Bytes) -> Result<BroadcastTx<'a>, BytesError> {
+        let header = SegmentHeader::init(bytes, RegionKind::Broadcast)?;
+        let length = check_buffer_length(header.capacity)?;
+        let tx = BroadcastTx::new(BytesAtomicView::from_bytes(HEADER_LENGTH, length, bytes));
+        SegmentHeader::mark_ready(bytes);
+        Ok(tx)
+    }
+
     fn max_msg_size(&self) -> u32 {
         (self.buffer.len() / 8) as u32
     }
@@ -130,9 +177,8 @@ impl<'a> BroadcastTx<'a> {
             tail_intent_counter.store(tail_intent, Release);
             // //ensure all writes above this fence happen before all write below the fence
             atomic::fence(Release);
-            let mut padding_buf = self
-                .buffer
-                .sub_view(record_offset, padding_size + HEADER_SIZE);
+            //the padding header is inside the padding
+            let mut padding_buf = self.buffer.sub_view(record_offset, padding_size);
             Self::write_header(PADDING_MSD_ID, &mut padding_buf, padding_size);
 
             //record_offset wraps for actual data
@@ -166,6 +212,16 @@ impl<'a> BroadcastTx<'a> {
     }
 }
 
+// buffer must be a power of 2 capacity plus the trailer
+fn check_buffer_length(length: u64) -> Result<usize, BytesError> {
+    let capacity = length.saturating_sub(TRAILER_SIZE as u64);
+    if capacity > u32::MAX as u64 || !capacity.is_power_of_two() {
+        return Err(BytesError::InvalidSize(length));
+    }
+    Ok(length as usize)
+}
+
+#[cfg(debug_assertions)]
 #[inline]
 fn is_aligned8(val: u64) -> bool {
     0 == val.bitand(7) // check aligned to 8
@@ -194,19 +250,22 @@ pub enum RxErr {
 }
 #[cfg(target_has_atomic = "64")]
 pub struct BroadcastRx<'a> {
-    counters: CountersInner<'a>,
-    buffer: BytesAtomicView<'a>,
+    counters: RxCounters<'a>,
+    buffer: BytesReadView<'a>,
     cursor: u64,
     lapped_count: u64,
 }
 
 impl<'a> BroadcastRx<'a> {
-    pub fn new(buffer: BytesAtomicView<'a>) -> BroadcastRx<'a> {
+    /// receivers only need read access, buffer can be a `BytesReadView`
+    /// over read only bytes or a `BytesAtomicView`
+    pub fn new<V: Into<BytesReadView<'a>>>(buffer: V) -> BroadcastRx<'a> {
+        let buffer: BytesReadView<'a> = buffer.into();
         let capacity: u32 = (buffer.len() - TRAILER_SIZE) as u32;
         assert!(capacity.is_power_of_two(), "capacity must be pow 2");
 
-        let inner = CountersInner::new(buffer.sub_slice(capacity..));
-        let start_position = inner.latest_record_counter().load(Acquire);
+        let inner = RxCounters::new(buffer.sub_slice(capacity..));
+        let start_position = inner.latest_record_counter(Acquire);
         BroadcastRx {
             counters: inner,
             buffer: buffer.sub_view(0, capacity),
@@ -215,31 +274,41 @@ impl<'a> BroadcastRx<'a> {
         }
     }
 
+    /// attach to a broadcast buffer set up by `BroadcastTx::create`, fails if bytes
+    /// does not hold a ready broadcast segment
+    pub fn attach(bytes: &'a Bytes) -> Result<BroadcastRx<'a>, BytesError> {
+        let header = SegmentHeader::validate(bytes, RegionKind::Broadcast)?;
+        let length = check_buffer_length(header.capacity)?;
+        Ok(BroadcastRx::new(BytesReadView::from_bytes(
+            HEADER_LENGTH,
+            length,
+            bytes,
+        )))
+    }
+
     pub fn lapped_count(&self) -> u64 {
         self.lapped_count
     }
     pub fn receive_next<F>(&mut self, mut read_callback: F) -> Result<u32, RxErr>
     where
-        F: FnMut(MsgTypeId, BytesAtomicView),
+        F: FnMut(MsgTypeId, BytesReadView),
     {
         let buffer = &self.buffer;
         let capacity = self.buffer.len();
         debug_assert!(capacity.is_power_of_two(), "capacity must be pow 2");
-        let tail_counter = self.counters.tail_counter();
-        let tail_intent_counter = self.counters.tail_intent_counter();
-        let latest_counter = self.counters.latest_record_counter();
-        let tail = tail_counter.load(Acquire);
+        let tail = self.counters.tail_counter(Acquire);
 
-        if tail == self.cursor {
+        //the latest record counter is published before the tail, after a lap the cursor
+        //can be ahead of the tail observed so far until the transmitter commits it
+        if tail <= self.cursor {
             return Err(RxErr::NoElement);
         }
-        assert!(tail > self.cursor, "invalid state cursor cannot be > tail");
 
-        let tail_intent_position = tail_intent_counter.load(Acquire);
+        let tail_intent_position = self.counters.tail_intent_counter(Acquire);
         let is_valid = (self.cursor + capacity as u64) > tail_intent_position;
         if !is_valid {
             self.lapped_count += 1;
-            self.cursor = latest_counter.load(Acquire);
+            self.cursor = self.counters.latest_record_counter(Acquire);
         }
         let record_offset = self.cursor.bitand(capacity as u64 - 1);
         let record_size: u32 = buffer.load_at(record_offset as usize, Relaxed);
@@ -247,7 +316,9 @@ impl<'a> BroadcastRx<'a> {
         let next_record_position = self.cursor + aligned_record_size as u64;
         let msg_id: u32 = buffer.load_at(record_offset as usize + 4, Relaxed);
         if PADDING_MSD_ID.inner() == msg_id {
-            let record_offset = next_record_position;
+            //the record after the padding is at the start of the buffer
+            let record_position = next_record_position;
+            let record_offset = record_position.bitand(capacity as u64 - 1);
             let record_size: u32 = buffer.load_at(record_offset as usize, Relaxed);
             let aligned_record_size: u32 = align(record_size, RECORD_ALIGNMENT);
             let msg_id: u32 = buffer.load_at(record_offset as usize + 4, Relaxed);
@@ -256,7 +327,7 @@ impl<'a> BroadcastRx<'a> {
                 PADDING_MSD_ID.inner(),
                 "cannot have two consecutive paddings"
             );
-            let next_record_position = record_offset + aligned_record_size as u64;
+            let next_record_position = record_position + aligned_record_size as u64;
             let start = (record_offset + HEADER_SIZE as u64) as u32;
 
             let data_buffer = buffer.sub_view(start, record_size - HEADER_SIZE);
@@ -277,8 +348,7 @@ impl<'a> BroadcastRx<'a> {
     fn commit_read(&mut self, record_size: u32, next_record_position: u64) -> Result<u32, RxErr> {
         //need to ensure reads / writes above this fence happen before any subsequent reads
         atomic::fence(Acquire);
-        let tail_intent_counter = self.counters.tail_intent_counter();
-        let tail_intent_position = tail_intent_counter.load(Acquire);
+        let tail_intent_position = self.counters.tail_intent_counter(Acquire);
         let read_ok = (self.cursor + self.buffer.len() as u64) > tail_intent_position;
 
         if read_ok {
@@ -289,7 +359,7 @@ impl<'a> BroadcastRx<'a> {
             self.cursor = next_record_position;
             Ok(record_size)
         } else {
-            let latest_record = self.counters.latest_record_counter().load(Acquire);
+            let latest_record = self.counters.latest_record_counter(Acquire);
             self.cursor = latest_record;
             self.lapped_count += 1;
             Err(Overwritten)
@@ -297,17 +367,105 @@ impl<'a> BroadcastRx<'a> {
     }
 }
 
+/// `BroadcastTx` keeping the bytes alive through an `Arc`, it is 'static so can be moved
+/// to long lived threads or tasks. transmit is the same as for `BroadcastTx`
+#[cfg(target_has_atomic = "64")]
+pub struct OwnedBroadcastTx {
+    //borrows from bytes, declared first so it is dropped before the reference count
+    tx: BroadcastTx<'static>,
+    _bytes: Arc<Bytes>,
+}
+
+impl OwnedBroadcastTx {
+    pub fn new(buffer: OwnedAtomicView) -> OwnedBroadcastTx {
+        //tx never hands out references which outlive it, and bytes outlives tx
+        let tx = BroadcastTx::new(unsafe { buffer.view_unbound() });
+        OwnedBroadcastTx {
+            tx,
+            _bytes: buffer.bytes().clone(),
+        }
+    }
+
+    /// see `BroadcastTx::create`
+    pub fn create(bytes: Arc<Bytes>) -> Result<OwnedBroadcastTx, BytesError> {
+        let header = SegmentHeader::init(&bytes, RegionKind::Broadcast)?;
+        let length = check_buffer_length(header.capacity)?;
+        let tx = OwnedBroadcastTx::new(OwnedAtomicView::from_bytes(
+            HEADER_LENGTH,
+            length,
+            bytes.clone(),
+        ));
+        SegmentHeader::mark_ready(&bytes);
+        Ok(tx)
+    }
+
+    #[inline]
+    pub fn transmit<F>(&mut self, msg_size: u32, id: MsgTypeId, f: F) -> Result<u32, TxErr>
+    where
+        F: Fn(BytesAtomicView) -> usize,
+    {
+        self.tx.transmit(msg_size, id, f)
+    }
+}
+
+/// `BroadcastRx` keeping the bytes alive through an `Arc`
+#[cfg(target_has_atomic = "64")]
+pub struct OwnedBroadcastRx {
+    //borrows from bytes, declared first so it is dropped before the reference count
+    rx: BroadcastRx<'static>,
+    _bytes: Arc<Bytes>,
+}
+
+impl OwnedBroadcastRx {
+    pub fn new<V: Into<OwnedReadView>>(buffer: V) -> OwnedBroadcastRx {
+        let buffer: OwnedReadView = buffer.into();
+        let rx = BroadcastRx::new(unsafe { buffer.view_unbound() });
+        OwnedBroadcastRx {
+            rx,
+            _bytes: buffer.bytes().clone(),
+        }
+    }
+
+    /// see `BroadcastRx::attach`
+    pub fn attach(bytes: Arc<Bytes>) -> Result<OwnedBroadcastRx, BytesError> {
+        let header = SegmentHeader::validate(&bytes, RegionKind::Broadcast)?;
+        let length = check_buffer_length(header.capacity)?;
+        Ok(OwnedBroadcastRx::new(OwnedReadView::from_bytes(
+            HEADER_LENGTH,
+            length,
+            bytes,
+        )))
+    }
+
+    pub fn lapped_count(&self) -> u64 {
+        self.rx.lapped_count()
+    }
+
+    #[inline]
+    pub fn receive_next<F>(&mut self, read_callback: F) -> Result<u32, RxErr>
+    where
+        F: FnMut(MsgTypeId, BytesReadView),
+    {
+        self.rx.receive_next(read_callback)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::broadcast::RxErr::NoElement;
     use crate::broadcast::{
-        align, BroadcastRx, BroadcastTx, MsgTypeId, RxErr, HEADER_SIZE, TRAILER_SIZE,
+        align, BroadcastRx, BroadcastTx, MsgTypeId, OwnedBroadcastRx, OwnedBroadcastTx, RxErr,
+        HEADER_SIZE, TRAILER_SIZE,
+    };
+    use crate::bytes::{
+        AtomicLoad, Bytes, BytesAtomicView, BytesError, BytesReadView, LoadStore, OwnedAtomicView,
     };
-    use crate::bytes::{Bytes, BytesAtomicView, LoadStore};
+    use crate::header::{HeaderError, RegionKind, SegmentHeader, HEADER_LENGTH};
     use rand::Rng;
     use std::cmp::max;
     use std::sync::atomic::AtomicBool;
     use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+    use std::sync::Arc;
     use std::thread;
 
     #[test]
@@ -345,7 +503,7 @@ mod tests {
                     assert_eq!(y, expected_val);
                     assert!(expected_val > previous_val);
                     read_count += 1;
-                    max_gap = max(max_gap, (expected_val - previous_val));
+                    max_gap = max(max_gap, expected_val - previous_val);
                     previous_val = expected_val;
                 }
                 Err(RxErr::Overwritten) => {}
@@ -366,7 +524,7 @@ mod tests {
         let mut tx = BroadcastTx::new(buffer.clone());
         let rx_0 = BroadcastRx::new(buffer.clone());
         let rx_1 = BroadcastRx::new(buffer.clone());
-        let max_count = 1000_000;
+        let max_count = if cfg!(miri) { 1_000 } else { 1_000_000 };
         let stop = AtomicBool::new(false);
         thread::scope(|s| {
             s.spawn(|| do_read(rx_0, &stop)); //start two reading threads
@@ -392,6 +550,79 @@ mod tests {
             stop.store(true, Release);
         });
     }
+    #[test]
+    fn test_owned_send_receive_across_threads() {
+        let bytes = Arc::new(Bytes::heap_allocate(HEADER_LENGTH + 1024 + TRAILER_SIZE));
+        let mut tx = OwnedBroadcastTx::create(bytes.clone()).unwrap();
+        let mut rx = OwnedBroadcastRx::attach(bytes.clone()).unwrap();
+        let max_count = if cfg!(miri) { 200 } else { 10_000u32 };
+        let reader = thread::spawn(move || {
+            let mut last_id = 0;
+            while last_id < max_count {
+                let (mut id, mut val) = (0, 0);
+                let res = rx.receive_next(|msg_id, buff| {
+                    id = msg_id.inner();
+                    val = buff.load_at(0, Relaxed);
+                });
+                match res {
+                    Ok(_) => {
+                        assert_eq!(id, val);
+                        assert!(id > last_id);
+                        last_id = id;
+                    }
+                    Err(NoElement) => thread::yield_now(),
+                    Err(RxErr::Overwritten) => {}
+                }
+            }
+        });
+        //the sender is 'static too, it can be moved to its own thread
+        let writer = thread::spawn(move || {
+            for i in 1..=max_count {
+                let res = tx.transmit(8, MsgTypeId(i), |mut buff| {
+                    buff.store_at(0, i, Relaxed);
+                    8
+                });
+                assert_eq!(Ok(16), res);
+            }
+        });
+        writer.join().unwrap();
+        reader.join().unwrap();
+        assert_eq!(1, Arc::strong_count(&bytes));
+
+        let buffer = OwnedAtomicView::from_bytes(
+            0,
+            64 + TRAILER_SIZE,
+            Arc::new(Bytes::heap_allocate(64 + TRAILER_SIZE)),
+        );
+        let mut tx = OwnedBroadcastTx::new(buffer.clone());
+        let mut rx = OwnedBroadcastRx::new(buffer);
+        assert!(tx.transmit(8, MsgTypeId(3), |_| 8).is_ok());
+        assert_eq!(Ok(16), rx.receive_next(|_, _| {}));
+    }
+
+    #[test]
+    fn test_receive_after_padding_on_later_lap() {
+        let bytes = Bytes::heap_allocate(256 + TRAILER_SIZE);
+        let buffer = BytesAtomicView::from_bytes(0, bytes.capacity(), &bytes);
+        let mut tx = BroadcastTx::new(buffer.clone());
+        let mut rx = BroadcastRx::new(buffer.clone());
+        //24 byte records, every 11th one wraps and leaves 16 bytes of padding
+        for i in 1..=40u32 {
+            let res = tx.transmit(16, MsgTypeId(i), |mut buff| {
+                buff.store_at(0, i, Relaxed);
+                16
+            });
+            assert!(res.is_ok());
+            let mut val = 0;
+            let res = rx.receive_next(|id, slice| {
+                assert_eq!(i, id.inner());
+                val = slice.load_at(0, Relaxed);
+            });
+            assert_eq!(Ok(24), res);
+            assert_eq!(i, val);
+        }
+    }
+
     #[test]
     fn test_send_receive() {
         let bytes = Bytes::heap_allocate(32 + TRAILER_SIZE);
@@ -400,8 +631,8 @@ mod tests {
         let mut rx = BroadcastRx::new(buffer.clone());
         let msg_id = MsgTypeId(1);
         let res = tx.transmit(4u32, msg_id, |mut bytes| {
-            *(&mut bytes[0]) = 0xFFu8;
-            *(&mut bytes[1]) = 0xF0u8;
+            bytes.put_u8(0, 0xFFu8);
+            bytes.put_u8(1, 0xF0u8);
             2
         });
         assert_eq!(Ok(16), res);
@@ -410,8 +641,8 @@ mod tests {
         let mut two = 0;
         let res = rx.receive_next(|id, slice| {
             assert_eq!(1, id.0);
-            one = slice[0];
-            two = slice[1];
+            one = slice.get_u8(0);
+            two = slice.get_u8(1);
             assert_eq!(4, slice.len());
         });
         assert_eq!(Ok(12), res, "unsuccessful read");
@@ -429,8 +660,8 @@ mod tests {
         for i in 1..5 {
             let msg_id = MsgTypeId(i);
             let res = tx.transmit(4u32, msg_id, |mut bytes| {
-                *(&mut bytes[0]) = i as u8;
-                *(&mut bytes[1]) = i as u8;
+                bytes.put_u8(0, i as u8);
+                bytes.put_u8(1, i as u8);
                 2
             });
             assert!(res.is_ok());
@@ -439,8 +670,8 @@ mod tests {
         //next receive should receive the latest message
         let res = rx.receive_next(|id, slice| {
             assert_eq!(4, id.0);
-            assert_eq!(4, slice[0]);
-            assert_eq!(4, slice[1]);
+            assert_eq!(4, slice.get_u8(0));
+            assert_eq!(4, slice.get_u8(1));
         });
         assert_eq!(Ok(4 + HEADER_SIZE), res);
     }
@@ -453,8 +684,8 @@ mod tests {
         let mut rx = BroadcastRx::new(buffer.clone());
         let msg_id = MsgTypeId(1);
         let res = tx.transmit(4u32, msg_id, |mut bytes| {
-            *(&mut bytes[0]) = 1 as u8;
-            *(&mut bytes[1]) = 1 as u8;
+            bytes.put_u8(0, 1u8);
+            bytes.put_u8(1, 1u8);
             2
         });
         assert!(res.is_ok());
@@ -464,8 +695,8 @@ mod tests {
             let tx = &mut tx;
             for i in 1..5 {
                 let _ = tx.transmit(4u32, MsgTypeId(i), |mut bytes| {
-                    *(&mut bytes[0]) = i as u8;
-                    *(&mut bytes[1]) = i as u8;
+                    bytes.put_u8(0, i as u8);
+                    bytes.put_u8(1, i as u8);
                     2
                 });
             }
@@ -475,11 +706,121 @@ mod tests {
 
         let res = rx.receive_next(|id, slice| {
             assert_eq!(id, MsgTypeId(4));
-            assert_eq!(4, slice[0]);
-            assert_eq!(4, slice[1]);
+            assert_eq!(4, slice.get_u8(0));
+            assert_eq!(4, slice.get_u8(1));
         });
         assert!(res.is_ok());
     }
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_receiver_attached_to_opened_file() {
+        let path = format!("/dev/shm/crossbytes.test.broadcast.{}", std::process::id());
+        let _ = std::fs::remove_file(&path);
+        let tx_bytes = Bytes::from_file_backed(&path, (32 + TRAILER_SIZE) as u64);
+        let rx_bytes = Bytes::open_file_backed(&path);
+        let mut tx = BroadcastTx::new(BytesAtomicView::from_bytes(
+            0,
+            tx_bytes.capacity(),
+            &tx_bytes,
+        ));
+        let mut rx = BroadcastRx::new(BytesAtomicView::from_bytes(
+            0,
+            rx_bytes.capacity(),
+            &rx_bytes,
+        ));
+        let res = tx.transmit(4u32, MsgTypeId(7), |mut bytes| {
+            bytes.store_at(0, 77u32, Relaxed);
+            4
+        });
+        assert!(res.is_ok());
+        let mut val = 0u32;
+        let res = rx.receive_next(|id, slice| {
+            assert_eq!(MsgTypeId(7), id);
+            val = slice.load_at(0, Relaxed);
+        });
+        assert_eq!(Ok(4 + HEADER_SIZE), res);
+        assert_eq!(77, val);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_receiver_on_read_only_bytes() {
+        let path = format!(
+            "/dev/shm/crossbytes.test.broadcast.ro.{}",
+            std::process::id()
+        );
+        let _ = std::fs::remove_file(&path);
+        let tx_bytes = Bytes::from_file_backed(&path, (32 + TRAILER_SIZE) as u64);
+        let rx_bytes = Bytes::open_file_backed_read_only(&path);
+        std::fs::remove_file(&path).unwrap();
+        let mut tx = BroadcastTx::new(BytesAtomicView::from_bytes(
+            0,
+            tx_bytes.capacity(),
+            &tx_bytes,
+        ));
+        let mut rx = BroadcastRx::new(BytesReadView::from_bytes(0, rx_bytes.capacity(), &rx_bytes));
+        let res = tx.transmit(4u32, MsgTypeId(3), |mut bytes| {
+            bytes.store_at(0, 33u32, Relaxed);
+            4
+        });
+        assert!(res.is_ok());
+        let mut val = 0u32;
+        let res = rx.receive_next(|id, slice| {
+            assert_eq!(MsgTypeId(3), id);
+            val = slice.load_at(0, Relaxed);
+        });
+        assert_eq!(Ok(4 + HEADER_SIZE), res);
+        assert_eq!(33, val);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_create_and_attach() {
+        let path = format!(
+            "/dev/shm/crossbytes.test.broadcast.attach.{}",
+            std::process::id()
+        );
+        let _ = std::fs::remove_file(&path);
+        let size = (HEADER_LENGTH + 64 + TRAILER_SIZE) as u64;
+        let tx_bytes = Bytes::from_file_backed(&path, size);
+        let not_ready = Bytes::open_file_backed_read_only(&path);
+        assert!(matches!(
+            BroadcastRx::attach(&not_ready),
+            Err(BytesError::InvalidHeader(HeaderError::BadMagic(0)))
+        ));
+
+        let mut tx = BroadcastTx::create(&tx_bytes).unwrap();
+        let rx_bytes = Bytes::open_validated_read_only(&path, RegionKind::Broadcast).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut rx = BroadcastRx::attach(&rx_bytes).unwrap();
+        let res = tx.transmit(8u32, MsgTypeId(5), |mut bytes| {
+            bytes.store_at(0, 55u64, Relaxed);
+            8
+        });
+        assert!(res.is_ok());
+        let mut val = 0u64;
+        let res = rx.receive_next(|_, slice| val = slice.load_at(0, Relaxed));
+        assert_eq!(Ok(8 + HEADER_SIZE), res);
+        assert_eq!(55, val);
+    }
+
+    #[test]
+    fn test_attach_refuses_other_kind() {
+        let bytes = Bytes::heap_allocate(HEADER_LENGTH + 64 + TRAILER_SIZE);
+        SegmentHeader::init(&bytes, RegionKind::Raw).unwrap();
+        SegmentHeader::mark_ready(&bytes);
+        assert!(matches!(
+            BroadcastRx::attach(&bytes),
+            Err(BytesError::InvalidHeader(HeaderError::KindMismatch { .. }))
+        ));
+        let bad_size = Bytes::heap_allocate(HEADER_LENGTH + 48 + TRAILER_SIZE);
+        assert!(matches!(
+            BroadcastTx::create(&bad_size),
+            Err(BytesError::InvalidSize(_))
+        ));
+    }
+
     #[test]
     fn test_late_joiner_read_latest() {
         let bytes = Bytes::heap_allocate(32 + TRAILER_SIZE);
@@ -488,8 +829,8 @@ mod tests {
         for i in 1..5 {
             let msg_id = MsgTypeId(i);
             let res = tx.transmit(4u32, msg_id, |mut bytes| {
-                *(&mut bytes[0]) = i as u8;
-                *(&mut bytes[1]) = i as u8;
+                bytes.put_u8(0, i as u8);
+                bytes.put_u8(1, i as u8);
                 2
             });
             assert!(res.is_ok());
@@ -500,8 +841,8 @@ mod tests {
         //next receive should receive the latest message
         let res = rx.receive_next(|id, slice| {
             assert_eq!(4, id.0);
-            assert_eq!(4, slice[0]);
-            assert_eq!(4, slice[1]);
+            assert_eq!(4, slice.get_u8(0));
+            assert_eq!(4, slice.get_u8(1));
         });
         assert_eq!(Ok(4 + HEADER_SIZE), res);
     }
@@ -1,181 +1,2272 @@
-use memmap::MmapMut;
+use crate::header::{HeaderError, RegionKind, SegmentHeader};
+use crate::overlay::{Overlay, OverlayView};
+use crate::segments::OwnerLock;
+#[cfg(target_os = "linux")]
+use memmap2::RemapOptions;
+use memmap2::{Mmap, MmapMut, MmapOptions};
 use std::alloc;
 use std::alloc::Layout;
-use std::fs::File;
-use std::ops::{Deref, DerefMut, RangeFrom};
-use std::path::Path;
+use std::fmt::{Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::ops::RangeFrom;
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use std::path::{Path, PathBuf};
 use std::ptr::NonNull;
 use std::sync::atomic::{
-    AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicU16, AtomicU32, AtomicU64,
-    AtomicU8, Ordering,
+    AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32,
+    AtomicU64, AtomicU8, AtomicUsize, Ordering,
 };
+use std::sync::Arc;
 
 enum MemType {
-    Heap(NonNull<u8>),
+    // layout is kept so the memory is deallocated with the alignment it was allocated with
+    Heap(NonNull<u8>, Layout),
+    // file backed mapping handed to Bytes::memap, the file is owned by the caller
     Mapped(MmapMut),
+    // anonymous mapping, there is no backing store to flush to
+    Anon(MmapMut),
+    // mapping of a file or shm segment, file is kept for operations on the backing store
+    File(MmapMut, File),
+    // PROT_READ mapping, can only be accessed through read only views
+    ReadOnly(Mmap, File),
+    // anonymous memfd mapping, fd is kept so it can be shared with other processes
+    #[cfg(target_os = "linux")]
+    MemFd(MmapMut, OwnedFd),
+    // memory owned by another allocator, released by calling the drop fn with ptr and len
+    External(NonNull<u8>, Option<DropFn>),
 }
+
+type DropFn = Box<dyn FnOnce(*mut u8, usize) + Send>;
 impl MemType {
     //converting *const u8 to *mut u8
     unsafe fn as_ptr(&self) -> *mut u8 {
         match &self {
-            MemType::Heap(ptr) => ptr.as_ptr(),
-            MemType::Mapped(memmap) => memmap.as_ptr() as *mut u8,
+            MemType::Heap(ptr, _) => ptr.as_ptr(),
+            MemType::Mapped(memmap) | MemType::Anon(memmap) => memmap.as_ptr() as *mut u8,
+            MemType::File(memmap, _) => memmap.as_ptr() as *mut u8,
+            MemType::ReadOnly(memmap, _) => memmap.as_ptr() as *mut u8,
+            #[cfg(target_os = "linux")]
+            MemType::MemFd(memmap, _) => memmap.as_ptr() as *mut u8,
+            MemType::External(ptr, _) => ptr.as_ptr(),
+        }
+    }
+}
+pub struct Bytes {
+    bytes: MemType,
+    cap: usize,
+    page_mode: PageMode,
+    locked: bool,
+    unlink_on_drop: Option<Segment>,
+    // held while this process owns the segment, released after it is unlinked
+    owner_lock: Option<OwnerLock>,
+    // incremented every time the memory is remapped
+    generation: u64,
+}
+
+/// named backing store which can be removed from the file system
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum Segment {
+    File(PathBuf),
+    #[cfg(target_os = "linux")]
+    Shm(String),
+}
+
+impl Segment {
+    pub(crate) fn unlink(&self) {
+        let _ = match self {
+            Segment::File(path) => std::fs::remove_file(path),
+            #[cfg(target_os = "linux")]
+            Segment::Shm(name) => crate::shm::unlink(name),
+        };
+    }
+}
+
+/// placement of the pages backing a `Bytes` on numa nodes, see `Bytes::set_numa_policy`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NumaPolicy {
+    // allocate only on the given nodes
+    Bind(Vec<u32>),
+    // spread pages round robin over the given nodes
+    Interleave(Vec<u32>),
+    // allocate on the node, falling back to other nodes when it is full
+    Preferred(u32),
+}
+
+/// page size in effect for the memory backing a `Bytes`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PageMode {
+    Regular,
+    // explicit huge pages, MAP_HUGETLB or a file on hugetlbfs
+    HugeTlb,
+    // transparent huge pages requested with madvise(MADV_HUGEPAGE)
+    Transparent,
+}
+
+#[derive(Debug)]
+pub enum BytesError {
+    // backing segment already exists, eg another process created it first
+    AlreadyExists,
+    // backing segment does not exist
+    NotFound,
+    PermissionDenied,
+    // no space left on the backing device, eg /dev/shm is full
+    NoSpace,
+    // heap allocation of the given size failed
+    OutOfMemory(usize),
+    // requested size is zero or too large for the backing type
+    InvalidSize(u64),
+    // alignment is not a power of two
+    InvalidAlignment(usize),
+    // address of memory handed over which is not aligned for u64 atomics
+    Misaligned(usize),
+    // pointer handed over is null
+    NullPointer,
+    // existing segment does not have the expected size
+    SizeMismatch { expected: u64, actual: u64 },
+    // operation is not supported by the backing type
+    Unsupported(&'static str),
+    // range is outside of the bytes
+    OutOfBounds { offset: usize, len: usize },
+    // segment header is missing or does not match, see `SegmentHeader`
+    InvalidHeader(HeaderError),
+    Io(io::Error),
+}
+
+impl From<io::Error> for BytesError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::AlreadyExists => BytesError::AlreadyExists,
+            io::ErrorKind::NotFound => BytesError::NotFound,
+            io::ErrorKind::PermissionDenied => BytesError::PermissionDenied,
+            io::ErrorKind::StorageFull => BytesError::NoSpace,
+            _ => BytesError::Io(err),
+        }
+    }
+}
+
+impl Display for BytesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BytesError::AlreadyExists => write!(f, "segment already exists"),
+            BytesError::NotFound => write!(f, "segment not found"),
+            BytesError::PermissionDenied => write!(f, "permission denied"),
+            BytesError::NoSpace => write!(f, "no space left on device"),
+            BytesError::OutOfMemory(size) => write!(f, "failed to allocate {} bytes", size),
+            BytesError::InvalidSize(size) => write!(f, "invalid size {}", size),
+            BytesError::InvalidAlignment(align) => write!(f, "invalid alignment {}", align),
+            BytesError::Misaligned(addr) => write!(f, "address {:#x} not 8 byte aligned", addr),
+            BytesError::NullPointer => write!(f, "null pointer"),
+            BytesError::SizeMismatch { expected, actual } => {
+                write!(f, "size mismatch expected={}, actual={}", expected, actual)
+            }
+            BytesError::Unsupported(op) => write!(f, "{} not supported by backing type", op),
+            BytesError::OutOfBounds { offset, len } => {
+                write!(f, "out of bounds offset={}, len={}", offset, len)
+            }
+            BytesError::InvalidHeader(err) => write!(f, "invalid segment header: {}", err),
+            BytesError::Io(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BytesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BytesError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// invalid access at an offset of a view
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AccessError {
+    // offset + size of the type is past the end of the view
+    OutOfBounds {
+        offset: usize,
+        size: usize,
+        length: usize,
+    },
+    // the address at offset is not a multiple of the alignment of the type
+    Misaligned {
+        offset: usize,
+        align: usize,
+    },
+}
+
+impl Display for AccessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessError::OutOfBounds {
+                offset,
+                size,
+                length,
+            } => write!(
+                f,
+                "bounds error offset={}, size={}, length={}",
+                offset, size, length
+            ),
+            AccessError::Misaligned { offset, align } => write!(
+                f,
+                "invalid alignment offset={}, Atomic type align={}",
+                offset, align
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AccessError {}
+
+// address of a T at offset of a view starting at data, the whole T must be inside the view
+#[inline]
+fn checked_ptr<T>(data: *const u8, length: usize, offset: usize) -> Result<*mut u8, AccessError> {
+    let ptr = checked_range(data, length, offset, size_of::<T>())?;
+    let align = align_of::<T>();
+    if (ptr as usize) & (align - 1) != 0 {
+        return Err(AccessError::Misaligned { offset, align });
+    }
+    Ok(ptr)
+}
+
+// address of size bytes at offset of a view starting at data, without alignment requirement
+#[inline]
+fn checked_range(
+    data: *const u8,
+    length: usize,
+    offset: usize,
+    size: usize,
+) -> Result<*mut u8, AccessError> {
+    if offset > length || length - offset < size {
+        return Err(AccessError::OutOfBounds {
+            offset,
+            size,
+            length,
+        });
+    }
+    Ok(data.wrapping_add(offset) as *mut u8)
+}
+
+// the memory of a view may be accessed concurrently, so every access is atomic. aligned
+// values use a single atomic of their width, anything else is copied a byte at a time
+#[inline]
+unsafe fn atomic_read<const N: usize>(ptr: *mut u8) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    let aligned = (ptr as usize) & (N - 1) == 0;
+    match N {
+        8 if aligned => bytes.copy_from_slice(
+            &AtomicU64::from_ptr(ptr as *mut u64)
+                .load(Ordering::Relaxed)
+                .to_ne_bytes(),
+        ),
+        4 if aligned => bytes.copy_from_slice(
+            &AtomicU32::from_ptr(ptr as *mut u32)
+                .load(Ordering::Relaxed)
+                .to_ne_bytes(),
+        ),
+        2 if aligned => bytes.copy_from_slice(
+            &AtomicU16::from_ptr(ptr as *mut u16)
+                .load(Ordering::Relaxed)
+                .to_ne_bytes(),
+        ),
+        _ => atomic_copy_to_slice(ptr, &mut bytes),
+    }
+    bytes
+}
+
+#[inline]
+unsafe fn atomic_write<const N: usize>(ptr: *mut u8, bytes: [u8; N]) {
+    let aligned = (ptr as usize) & (N - 1) == 0;
+    match N {
+        8 if aligned => {
+            let val = u64::from_ne_bytes(bytes[..8].try_into().unwrap());
+            AtomicU64::from_ptr(ptr as *mut u64).store(val, Ordering::Relaxed)
+        }
+        4 if aligned => {
+            let val = u32::from_ne_bytes(bytes[..4].try_into().unwrap());
+            AtomicU32::from_ptr(ptr as *mut u32).store(val, Ordering::Relaxed)
+        }
+        2 if aligned => {
+            let val = u16::from_ne_bytes(bytes[..2].try_into().unwrap());
+            AtomicU16::from_ptr(ptr as *mut u16).store(val, Ordering::Relaxed)
+        }
+        _ => atomic_copy_from_slice(ptr, &bytes),
+    }
+}
+
+unsafe fn atomic_copy_to_slice(src: *const u8, dst: &mut [u8]) {
+    for (i, byte) in dst.iter_mut().enumerate() {
+        *byte = AtomicU8::from_ptr(src.add(i) as *mut u8).load(Ordering::Relaxed);
+    }
+}
+
+unsafe fn atomic_copy_from_slice(dst: *mut u8, src: &[u8]) {
+    for (i, byte) in src.iter().enumerate() {
+        AtomicU8::from_ptr(dst.add(i)).store(*byte, Ordering::Relaxed);
+    }
+}
+
+// byte by byte like memmove, src and dst may overlap
+unsafe fn atomic_copy(src: *const u8, dst: *mut u8, length: usize) {
+    let copy = |i: usize| {
+        let byte = AtomicU8::from_ptr(src.add(i) as *mut u8).load(Ordering::Relaxed);
+        AtomicU8::from_ptr(dst.add(i)).store(byte, Ordering::Relaxed);
+    };
+    if (dst as usize) > (src as usize) {
+        (0..length).rev().for_each(copy);
+    } else {
+        (0..length).for_each(copy);
+    }
+}
+
+/// length prefix of strings and byte arrays, an i32 in little endian as written by Agrona's
+/// putStringAscii and putStringUtf8
+pub const LENGTH_PREFIX_SIZE: usize = 4;
+
+// plain getters for views, (type, little endian, big endian, native endian)
+macro_rules! plain_get_impl {
+    ($(($type: ty, $get_le: ident, $get_be: ident, $get_ne: ident)),* $(,)?) => {
+        $(
+            pub fn $get_le(&self, offset: usize) -> $type {
+                <$type>::from_le_bytes(self.read_array(offset))
+            }
+
+            pub fn $get_be(&self, offset: usize) -> $type {
+                <$type>::from_be_bytes(self.read_array(offset))
+            }
+
+            pub fn $get_ne(&self, offset: usize) -> $type {
+                <$type>::from_ne_bytes(self.read_array(offset))
+            }
+        )*
+
+        pub fn get_u8(&self, offset: usize) -> u8 {
+            u8::from_ne_bytes(self.read_array(offset))
+        }
+
+        pub fn get_i8(&self, offset: usize) -> i8 {
+            i8::from_ne_bytes(self.read_array(offset))
+        }
+
+        /// length prefixed bytes at offset, written by `put_bytes`
+        pub fn get_bytes(&self, offset: usize) -> Vec<u8> {
+            //a negative length is out of bounds
+            let length = self.get_i32_le(offset) as u32 as usize;
+            //checked before allocating, a corrupt prefix must not allocate up to 4GiB
+            let data = unsafe { self.data_ptr() };
+            let start = offset + LENGTH_PREFIX_SIZE;
+            if let Err(err) = checked_range(data, self.length, start, length) {
+                access_failed(err);
+            }
+            let mut bytes = vec![0u8; length];
+            self.copy_to_slice(start, &mut bytes);
+            bytes
+        }
+
+        /// length prefixed ascii string at offset, bytes outside of ascii are replaced with U+FFFD
+        pub fn get_string_ascii(&self, offset: usize) -> String {
+            self.get_bytes(offset)
+                .into_iter()
+                .map(|byte| {
+                    if byte.is_ascii() {
+                        byte as char
+                    } else {
+                        char::REPLACEMENT_CHARACTER
+                    }
+                })
+                .collect()
+        }
+
+        /// length prefixed utf8 string at offset, invalid sequences are replaced with U+FFFD
+        pub fn get_string_utf8(&self, offset: usize) -> String {
+            match String::from_utf8(self.get_bytes(offset)) {
+                Ok(string) => string,
+                Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+            }
+        }
+
+        // relaxed read of N bytes at offset, which need not be aligned
+        #[inline]
+        fn read_array<const N: usize>(&self, offset: usize) -> [u8; N] {
+            let data = unsafe { self.data_ptr() };
+            match checked_range(data, self.length, offset, N) {
+                Ok(ptr) => unsafe { atomic_read(ptr) },
+                Err(err) => access_failed(err),
+            }
+        }
+    };
+}
+
+// plain setters for writable views, (type, little endian, big endian, native endian)
+macro_rules! plain_put_impl {
+    ($(($type: ty, $put_le: ident, $put_be: ident, $put_ne: ident)),* $(,)?) => {
+        $(
+            pub fn $put_le(&mut self, offset: usize, val: $type) {
+                self.write_array(offset, val.to_le_bytes())
+            }
+
+            pub fn $put_be(&mut self, offset: usize, val: $type) {
+                self.write_array(offset, val.to_be_bytes())
+            }
+
+            pub fn $put_ne(&mut self, offset: usize, val: $type) {
+                self.write_array(offset, val.to_ne_bytes())
+            }
+        )*
+
+        pub fn put_u8(&mut self, offset: usize, val: u8) {
+            self.write_array(offset, [val])
+        }
+
+        pub fn put_i8(&mut self, offset: usize, val: i8) {
+            self.write_array(offset, val.to_ne_bytes())
+        }
+
+        /// write the length of src followed by src at offset, returns the number of bytes written
+        /// # Panics
+        /// if the prefix and src do not fit in the view, before anything is written, or if src
+        /// is longer than i32::MAX
+        pub fn put_bytes(&mut self, offset: usize, src: &[u8]) -> usize {
+            let length = i32::try_from(src.len()).expect("length does not fit in the i32 prefix");
+            let data = unsafe { self.data_ptr() };
+            let size = LENGTH_PREFIX_SIZE + src.len();
+            if let Err(err) = checked_range(data, self.length, offset, size) {
+                access_failed(err);
+            }
+            self.put_i32_le(offset, length);
+            self.copy_from_slice(offset + LENGTH_PREFIX_SIZE, src);
+            LENGTH_PREFIX_SIZE + src.len()
+        }
+
+        /// length prefixed ascii string, chars outside of ascii are written as '?' like
+        /// Agrona's putStringAscii. returns the number of bytes written, panics like `put_bytes`
+        pub fn put_string_ascii(&mut self, offset: usize, value: &str) -> usize {
+            let ascii: Vec<u8> = value
+                .chars()
+                .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+                .collect();
+            self.put_bytes(offset, &ascii)
+        }
+
+        /// length prefixed utf8 string, returns the number of bytes written.
+        /// panics like `put_bytes`
+        pub fn put_string_utf8(&mut self, offset: usize, value: &str) -> usize {
+            self.put_bytes(offset, value.as_bytes())
+        }
+
+        /// set length bytes starting at offset to value
+        pub fn set_memory(&mut self, offset: usize, length: usize, value: u8) {
+            let data = unsafe { self.data_ptr() };
+            match checked_range(data, self.length, offset, length) {
+                Ok(ptr) => (0..length).for_each(|i| unsafe {
+                    AtomicU8::from_ptr(ptr.add(i)).store(value, Ordering::Relaxed)
+                }),
+                Err(err) => access_failed(err),
+            }
+        }
+
+        // relaxed write of N bytes at offset, which need not be aligned
+        #[inline]
+        fn write_array<const N: usize>(&mut self, offset: usize, bytes: [u8; N]) {
+            let data = unsafe { self.data_ptr() };
+            match checked_range(data, self.length, offset, N) {
+                Ok(ptr) => unsafe { atomic_write(ptr, bytes) },
+                Err(err) => access_failed(err),
+            }
+        }
+    };
+}
+
+// named orderings in the style of Agrona, (type, plain get, plain put, opaque get, opaque put,
+// acquire get, release put, volatile get, volatile put)
+macro_rules! ordered_access_impl {
+    ($(($type: ty, $get_plain: ident, $put_plain: ident, $get_opaque: ident, $put_opaque: ident,
+        $get_acquire: ident, $put_release: ident, $get_volatile: ident, $put_volatile: ident)),* $(,)?) => {
+        $(
+            /// native endian, offset need not be aligned, no ordering with other accesses
+            pub fn $get_plain(&self, offset: usize) -> $type {
+                <$type>::from_ne_bytes(self.read_array(offset))
+            }
+
+            pub fn $put_plain(&mut self, offset: usize, val: $type) {
+                self.write_array(offset, val.to_ne_bytes())
+            }
+
+            /// single atomic access, no ordering with other accesses
+            pub fn $get_opaque(&self, offset: usize) -> $type {
+                LoadStore::<$type>::load_at(self, offset, Ordering::Relaxed)
+            }
+
+            pub fn $put_opaque(&mut self, offset: usize, val: $type) {
+                LoadStore::<$type>::store_at(self, offset, val, Ordering::Relaxed)
+            }
+
+            pub fn $get_acquire(&self, offset: usize) -> $type {
+                LoadStore::<$type>::load_at(self, offset, Ordering::Acquire)
+            }
+
+            /// Agrona's putXxxOrdered
+            pub fn $put_release(&mut self, offset: usize, val: $type) {
+                LoadStore::<$type>::store_at(self, offset, val, Ordering::Release)
+            }
+
+            pub fn $get_volatile(&self, offset: usize) -> $type {
+                LoadStore::<$type>::load_at(self, offset, Ordering::SeqCst)
+            }
+
+            pub fn $put_volatile(&mut self, offset: usize, val: $type) {
+                LoadStore::<$type>::store_at(self, offset, val, Ordering::SeqCst)
+            }
+        )*
+    };
+}
+
+/// writable views which can be the destination of `copy_between_views`
+pub trait AsAtomicView {
+    fn as_atomic_view(&mut self) -> BytesAtomicView<'_>;
+}
+
+/// copy length bytes at src_offset of src to dst_offset of dst, the views may overlap.
+/// Agrona's putBytes(index, srcBuffer, srcIndex, length)
+pub fn copy_between_views<'b, S: Into<BytesReadView<'b>>, D: AsAtomicView>(
+    src: S,
+    src_offset: usize,
+    dst: &mut D,
+    dst_offset: usize,
+    length: usize,
+) {
+    let src = src.into();
+    let dst = dst.as_atomic_view();
+    let src_ptr = match checked_range(unsafe { src.data_ptr() }, src.length, src_offset, length) {
+        Ok(ptr) => ptr,
+        Err(err) => access_failed(err),
+    };
+    let dst_ptr = match checked_range(unsafe { dst.data_ptr() }, dst.length, dst_offset, length) {
+        Ok(ptr) => ptr,
+        Err(err) => access_failed(err),
+    };
+    unsafe { atomic_copy(src_ptr, dst_ptr, length) }
+}
+
+// panics unless size bytes at offset fit in a view of length, used when creating views
+#[inline]
+fn check_view_bounds(offset: usize, size: usize, length: usize) {
+    if offset.checked_add(size).is_none_or(|end| end > length) {
+        access_failed(AccessError::OutOfBounds {
+            offset,
+            size,
+            length,
+        });
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn access_failed(err: AccessError) -> ! {
+    panic!("{}", err)
+}
+
+impl Bytes {
+    pub fn from_file_backed<P: AsRef<Path>>(file: P, size: u64) -> Self {
+        Self::try_from_file_backed(file, size).expect("failed to create file backed bytes")
+    }
+
+    /// create a new file of size bytes and map it, fails if the file already exists
+    pub fn try_from_file_backed<P: AsRef<Path>>(file: P, size: u64) -> Result<Self, BytesError> {
+        if size == 0 || size > isize::MAX as u64 {
+            return Err(BytesError::InvalidSize(size));
+        }
+        let file = File::create_new(file)?;
+        file.set_len(size)?;
+        Self::try_from_file(file, Some(size))
+    }
+
+    pub fn create_owned<P: AsRef<Path>>(file: P, size: u64) -> Self {
+        Self::try_create_owned(file, size).expect("failed to create owned file backed bytes")
+    }
+
+    /// create a new file of size bytes owned by this `Bytes`, the owner lock is held until
+    /// drop and then the file is removed. if the owner dies without dropping it the file
+    /// is removed by `segments::cleanup_stale`
+    pub fn try_create_owned<P: AsRef<Path>>(file: P, size: u64) -> Result<Self, BytesError> {
+        let lock = OwnerLock::acquire(&file)?;
+        let mut bytes = Self::try_from_file_backed(&file, size)?;
+        bytes.set_unlink_on_drop(Some(Segment::File(file.as_ref().to_path_buf())));
+        bytes.set_owner_lock(Some(lock));
+        Ok(bytes)
+    }
+
+    pub fn open_file_backed<P: AsRef<Path>>(file: P) -> Self {
+        Self::try_open_file_backed(file, None).expect("failed to open file backed bytes")
+    }
+
+    /// map an existing file at its current length, eg one created by another process
+    /// with `from_file_backed`. if expected_size is given the file length must match it
+    pub fn try_open_file_backed<P: AsRef<Path>>(
+        file: P,
+        expected_size: Option<u64>,
+    ) -> Result<Self, BytesError> {
+        let file = OpenOptions::new().read(true).write(true).open(file)?;
+        Self::try_from_file(file, expected_size)
+    }
+
+    pub fn from_file(file: File) -> Self {
+        Self::try_from_file(file, None).expect("failed to map file")
+    }
+
+    /// map an already opened file at its current length, file must be opened read + write
+    pub fn try_from_file(file: File, expected_size: Option<u64>) -> Result<Self, BytesError> {
+        let mmap = map_file(&file, expected_size)?;
+        let cap = mmap.len();
+        let page_mode = file_page_mode(&file);
+        Ok(Bytes {
+            bytes: MemType::File(mmap, file),
+            cap,
+            page_mode,
+            locked: false,
+            unlink_on_drop: None,
+            owner_lock: None,
+            generation: 0,
+        })
+    }
+}
+
+fn file_page_mode(_file: &File) -> PageMode {
+    #[cfg(target_os = "linux")]
+    if crate::pages::is_hugetlbfs(_file) {
+        return PageMode::HugeTlb;
+    }
+    PageMode::Regular
+}
+
+// check the current length of file against expected_size if given
+fn check_file_size(file: &File, expected_size: Option<u64>) -> Result<(), BytesError> {
+    let actual = file.metadata()?.len();
+    if let Some(expected) = expected_size {
+        if expected != actual {
+            return Err(BytesError::SizeMismatch { expected, actual });
+        }
+    }
+    if actual == 0 || actual > isize::MAX as u64 {
+        return Err(BytesError::InvalidSize(actual));
+    }
+    Ok(())
+}
+
+/// map file at its current length, checking it against expected_size if given
+fn map_file(file: &File, expected_size: Option<u64>) -> Result<MmapMut, BytesError> {
+    check_file_size(file, expected_size)?;
+    let mmap = unsafe { MmapMut::map_mut(file) }?;
+    Ok(mmap)
+}
+
+impl Bytes {
+    pub fn open_file_backed_read_only<P: AsRef<Path>>(file: P) -> Self {
+        Self::try_open_file_backed_read_only(file, None).expect("failed to open file backed bytes")
+    }
+
+    /// map an existing file read only, it can only be accessed through `BytesReadView`
+    pub fn try_open_file_backed_read_only<P: AsRef<Path>>(
+        file: P,
+        expected_size: Option<u64>,
+    ) -> Result<Self, BytesError> {
+        let file = File::open(file)?;
+        Self::try_from_file_read_only(file, expected_size)
+    }
+
+    /// map an already opened file read only at its current length
+    pub fn try_from_file_read_only(
+        file: File,
+        expected_size: Option<u64>,
+    ) -> Result<Self, BytesError> {
+        check_file_size(&file, expected_size)?;
+        let mmap = unsafe { Mmap::map(&file) }?;
+        let cap = mmap.len();
+        let page_mode = file_page_mode(&file);
+        Ok(Bytes {
+            bytes: MemType::ReadOnly(mmap, file),
+            cap,
+            page_mode,
+            locked: false,
+            unlink_on_drop: None,
+            owner_lock: None,
+            generation: 0,
+        })
+    }
+
+    /// map an existing file and check it holds a ready segment of kind,
+    /// refuses to attach to a segment with a missing or mismatched `SegmentHeader`
+    pub fn open_validated<P: AsRef<Path>>(file: P, kind: RegionKind) -> Result<Self, BytesError> {
+        let bytes = Self::try_open_file_backed(file, None)?;
+        SegmentHeader::validate(&bytes, kind)?;
+        Ok(bytes)
+    }
+
+    pub fn open_validated_read_only<P: AsRef<Path>>(
+        file: P,
+        kind: RegionKind,
+    ) -> Result<Self, BytesError> {
+        let bytes = Self::try_open_file_backed_read_only(file, None)?;
+        SegmentHeader::validate(&bytes, kind)?;
+        Ok(bytes)
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        matches!(self.bytes, MemType::ReadOnly(_, _))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Bytes {
+    pub fn memfd_create(name: &str, size: u64, seal_size: bool) -> Self {
+        Self::try_memfd_create(name, size, seal_size).expect("failed to create memfd bytes")
+    }
+
+    /// create anonymous shared memory using memfd_create, name is only used for debugging.
+    /// share it with other processes by passing `fd()`, eg with `memfd::send_fd`.
+    /// if seal_size is set the size is sealed with F_SEAL_GROW and F_SEAL_SHRINK
+    pub fn try_memfd_create(name: &str, size: u64, seal_size: bool) -> Result<Self, BytesError> {
+        if size == 0 || size > isize::MAX as u64 {
+            return Err(BytesError::InvalidSize(size));
+        }
+        let fd = crate::memfd::create(name, size, seal_size)?;
+        Self::try_from_memfd(fd, Some(size))
+    }
+
+    pub fn from_memfd(fd: OwnedFd) -> Self {
+        Self::try_from_memfd(fd, None).expect("failed to map memfd")
+    }
+
+    /// map a memfd received from another process at its current length
+    pub fn try_from_memfd(fd: OwnedFd, expected_size: Option<u64>) -> Result<Self, BytesError> {
+        let file = File::from(fd);
+        let mmap = map_file(&file, expected_size)?;
+        let cap = mmap.len();
+        Ok(Bytes {
+            bytes: MemType::MemFd(mmap, file.into()),
+            cap,
+            page_mode: PageMode::Regular,
+            locked: false,
+            unlink_on_drop: None,
+            owner_lock: None,
+            generation: 0,
+        })
+    }
+
+    /// fd backing this memory, only available for file, shm and memfd backed bytes
+    pub fn fd(&self) -> Option<BorrowedFd<'_>> {
+        match &self.bytes {
+            MemType::File(_, file) => Some(file.as_fd()),
+            MemType::ReadOnly(_, file) => Some(file.as_fd()),
+            MemType::MemFd(_, fd) => Some(fd.as_fd()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Bytes {
+    pub fn shm_create(name: &str, size: u64) -> Self {
+        Self::try_shm_create(name, size).expect("failed to create shm segment")
+    }
+
+    /// create a new posix shared memory segment of size bytes using shm_open,
+    /// fails if the segment already exists. the segment is visible as /dev/shm/name
+    pub fn try_shm_create(name: &str, size: u64) -> Result<Self, BytesError> {
+        if size == 0 || size > isize::MAX as u64 {
+            return Err(BytesError::InvalidSize(size));
+        }
+        let file = crate::shm::open(name, true, 0o666)?;
+        let mapped = file
+            .set_len(size)
+            .map_err(BytesError::from)
+            .and_then(|_| map_file(&file, Some(size)));
+        match mapped {
+            Ok(mmap) => Ok(Bytes {
+                page_mode: file_page_mode(&file),
+                bytes: MemType::File(mmap, file),
+                cap: size as usize,
+                locked: false,
+                unlink_on_drop: None,
+                owner_lock: None,
+                generation: 0,
+            }),
+            Err(err) => {
+                //do not leave a half initialised segment behind
+                let _ = crate::shm::unlink(name);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn shm_open(name: &str) -> Self {
+        Self::try_shm_open(name, None).expect("failed to open shm segment")
+    }
+
+    /// map an existing posix shared memory segment at its current length
+    pub fn try_shm_open(name: &str, expected_size: Option<u64>) -> Result<Self, BytesError> {
+        let file = crate::shm::open(name, false, 0)?;
+        Self::try_from_file(file, expected_size)
+    }
+
+    pub fn shm_open_read_only(name: &str) -> Self {
+        Self::try_shm_open_read_only(name, None).expect("failed to open shm segment")
+    }
+
+    /// map an existing posix shared memory segment read only
+    pub fn try_shm_open_read_only(
+        name: &str,
+        expected_size: Option<u64>,
+    ) -> Result<Self, BytesError> {
+        let file = crate::shm::open_read_only(name)?;
+        Self::try_from_file_read_only(file, expected_size)
+    }
+
+    /// remove the named segment, processes which have it mapped can continue to use it
+    pub fn shm_unlink(name: &str) -> Result<(), BytesError> {
+        crate::shm::unlink(name)?;
+        Ok(())
+    }
+}
+
+/// default alignment of heap allocated bytes, one cache line so that atomics of any
+/// width can be placed at the start and no other allocation shares the first line
+pub const CACHE_LINE_SIZE: usize = 64;
+
+/// will heap allocate size bytes aligned to align
+/// ensure memory is zeroed out
+///
+fn heap_allocate(size: usize, align: usize) -> Result<(NonNull<u8>, Layout), BytesError> {
+    // zero sized allocations are UB with the global allocator
+    if size == 0 || size > isize::MAX as usize {
+        return Err(BytesError::InvalidSize(size as u64));
+    }
+    if !align.is_power_of_two() {
+        return Err(BytesError::InvalidAlignment(align));
+    }
+    let new_layout =
+        Layout::from_size_align(size, align).map_err(|_| BytesError::InvalidSize(size as u64))?;
+    let allocated_ptr = unsafe { alloc::alloc_zeroed(new_layout) };
+    // If allocation fails, `new_ptr` will be null
+    let ptr = NonNull::new(allocated_ptr).ok_or(BytesError::OutOfMemory(size))?;
+    Ok((ptr, new_layout))
+}
+unsafe impl Sync for Bytes {}
+unsafe impl Send for Bytes {}
+
+impl Bytes {
+    /// heap allocate size zeroed bytes aligned to `CACHE_LINE_SIZE`
+    pub fn heap_allocate(size: usize) -> Bytes {
+        Self::heap_allocate_aligned(size, CACHE_LINE_SIZE)
+    }
+
+    pub fn try_heap_allocate(size: usize) -> Result<Bytes, BytesError> {
+        Self::try_heap_allocate_aligned(size, CACHE_LINE_SIZE)
+    }
+
+    pub fn heap_allocate_aligned(size: usize, align: usize) -> Bytes {
+        match Self::try_heap_allocate_aligned(size, align) {
+            Ok(bytes) => bytes,
+            Err(BytesError::OutOfMemory(_)) => {
+                alloc::handle_alloc_error(Layout::from_size_align(size, align).unwrap())
+            }
+            Err(err) => panic!("failed to heap allocate: {}", err),
+        }
+    }
+
+    /// heap allocate size zeroed bytes, align must be a power of two eg a page size
+    pub fn try_heap_allocate_aligned(size: usize, align: usize) -> Result<Bytes, BytesError> {
+        let (ptr, layout) = heap_allocate(size, align)?;
+        Ok(Bytes {
+            bytes: MemType::Heap(ptr, layout),
+            cap: size,
+            page_mode: PageMode::Regular,
+            locked: false,
+            unlink_on_drop: None,
+            owner_lock: None,
+            generation: 0,
+        })
+    }
+    pub fn from_vec(vec: Vec<u8>) -> Bytes {
+        match Self::try_from_vec(vec) {
+            Ok(bytes) => bytes,
+            Err((err, _)) => panic!("failed to wrap vec: {}", err),
+        }
+    }
+
+    /// take ownership of the vec without copying, the allocation is released by the
+    /// global allocator on drop. the data must be aligned for u64 atomics,
+    /// on error the vec is handed back
+    pub fn try_from_vec(vec: Vec<u8>) -> Result<Bytes, (BytesError, Vec<u8>)> {
+        let mut vec = std::mem::ManuallyDrop::new(vec);
+        let (ptr, len, capacity) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+        let drop_fn = move |ptr, len| drop(unsafe { Vec::from_raw_parts(ptr, len, capacity) });
+        //not taken over, give the allocation back
+        unsafe { Self::from_raw_parts(ptr, len, drop_fn) }
+            .map_err(|err| (err, unsafe { Vec::from_raw_parts(ptr, len, capacity) }))
+    }
+
+    pub fn from_boxed_slice(boxed: Box<[u8]>) -> Bytes {
+        match Self::try_from_boxed_slice(boxed) {
+            Ok(bytes) => bytes,
+            Err((err, _)) => panic!("failed to wrap boxed slice: {}", err),
+        }
+    }
+
+    /// like `try_from_vec`, on error the boxed slice is handed back
+    pub fn try_from_boxed_slice(boxed: Box<[u8]>) -> Result<Bytes, (BytesError, Box<[u8]>)> {
+        let len = boxed.len();
+        let ptr = Box::into_raw(boxed) as *mut u8;
+        let into_box =
+            |ptr, len| unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) };
+        unsafe { Self::from_raw_parts(ptr, len, move |ptr, len| drop(into_box(ptr, len))) }
+            .map_err(|err| (err, into_box(ptr, len)))
+    }
+
+    /// wrap len bytes at ptr owned by another allocator or library, eg a region mapped
+    /// by a c library or a dma buffer. drop_fn is called with ptr and len when the bytes
+    /// are dropped. on error drop_fn is not called and the memory stays with the caller
+    ///
+    /// # Safety
+    /// ptr must be valid for reads and writes of len bytes until drop_fn is called, and
+    /// must not be accessed other than through atomics while the bytes are alive
+    pub unsafe fn from_raw_parts<F>(
+        ptr: *mut u8,
+        len: usize,
+        drop_fn: F,
+    ) -> Result<Bytes, BytesError>
+    where
+        F: FnOnce(*mut u8, usize) + Send + 'static,
+    {
+        if len == 0 || len > isize::MAX as usize {
+            return Err(BytesError::InvalidSize(len as u64));
+        }
+        let ptr = NonNull::new(ptr).ok_or(BytesError::NullPointer)?;
+        //views require every u64 offset which is a multiple of 8 to be aligned
+        if ptr.as_ptr().align_offset(align_of::<u64>()) != 0 {
+            return Err(BytesError::Misaligned(ptr.as_ptr() as usize));
+        }
+        Ok(Bytes {
+            bytes: MemType::External(ptr, Some(Box::new(drop_fn))),
+            cap: len,
+            page_mode: PageMode::Regular,
+            locked: false,
+            unlink_on_drop: None,
+            owner_lock: None,
+            generation: 0,
+        })
+    }
+
+    /// wrap a file backed mapping, anonymous mappings go through `memap_anon`
+    pub fn memap(memap: MmapMut) -> Bytes {
+        let cap = memap.len();
+        Self::wrap_mapping(MemType::Mapped(memap), cap)
+    }
+
+    /// wrap an anonymous mapping, which has nothing to flush to
+    pub fn memap_anon(memap: MmapMut) -> Bytes {
+        let cap = memap.len();
+        Self::wrap_mapping(MemType::Anon(memap), cap)
+    }
+
+    fn wrap_mapping(bytes: MemType, cap: usize) -> Bytes {
+        Bytes {
+            bytes,
+            cap,
+            page_mode: PageMode::Regular,
+            locked: false,
+            unlink_on_drop: None,
+            owner_lock: None,
+            generation: 0,
+        }
+    }
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+    /// number of times the memory was remapped, eg by `grow`.
+    /// offsets stay valid across a remap but addresses may not
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    pub fn page_mode(&self) -> PageMode {
+        self.page_mode
+    }
+
+    pub(crate) fn set_unlink_on_drop(&mut self, segment: Option<Segment>) {
+        self.unlink_on_drop = segment;
+    }
+
+    pub(crate) fn set_owner_lock(&mut self, lock: Option<OwnerLock>) {
+        self.owner_lock = lock;
+    }
+
+    /// true if this `Bytes` holds the owner lock of its segment, see `OwnerLock`
+    pub fn is_owner(&self) -> bool {
+        self.owner_lock.is_some()
+    }
+
+    /// alignment guaranteed for the start of the memory,
+    /// the allocation alignment for heap bytes and the page size for mappings
+    pub fn alignment(&self) -> usize {
+        match &self.bytes {
+            MemType::Heap(_, layout) => layout.align(),
+            //largest power of two the address is a multiple of
+            MemType::External(ptr, _) => {
+                let addr = ptr.as_ptr() as usize;
+                addr & addr.wrapping_neg()
+            }
+            #[cfg(target_os = "linux")]
+            _ => crate::pages::page_size(),
+            #[cfg(not(target_os = "linux"))]
+            _ => 4096,
+        }
+    }
+
+    /// write all modified pages back to the backing file (msync), blocking until done.
+    /// needed for data to survive a crash when the file is not on tmpfs
+    pub fn flush(&self) -> Result<(), BytesError> {
+        self.flush_range(0, self.cap)
+    }
+
+    /// write the modified pages of the range back to the backing file, blocking until done
+    pub fn flush_range(&self, offset: usize, len: usize) -> Result<(), BytesError> {
+        self.flush_with(offset, len, false)
+    }
+
+    /// start writing all modified pages back to the backing file without waiting
+    pub fn flush_async(&self) -> Result<(), BytesError> {
+        self.flush_async_range(0, self.cap)
+    }
+
+    pub fn flush_async_range(&self, offset: usize, len: usize) -> Result<(), BytesError> {
+        self.flush_with(offset, len, true)
+    }
+
+    fn flush_with(&self, offset: usize, len: usize, is_async: bool) -> Result<(), BytesError> {
+        if offset.checked_add(len).is_none_or(|end| end > self.cap) {
+            return Err(BytesError::OutOfBounds { offset, len });
+        }
+        let mmap = match &self.bytes {
+            MemType::Heap(_, _) | MemType::External(_, _) | MemType::Anon(_) => {
+                return Err(BytesError::Unsupported("flush"))
+            }
+            //nothing can have been modified through a read only mapping
+            MemType::ReadOnly(_, _) => return Ok(()),
+            MemType::Mapped(mmap) | MemType::File(mmap, _) => mmap,
+            #[cfg(target_os = "linux")]
+            MemType::MemFd(mmap, _) => mmap,
+        };
+        if is_async {
+            mmap.flush_async_range(offset, len)?;
+        } else {
+            mmap.flush_range(offset, len)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Bytes {
+    pub fn heap_allocate_huge(size: usize) -> Bytes {
+        Self::try_heap_allocate_huge(size).expect("failed to allocate huge pages")
+    }
+
+    /// allocate size bytes of zeroed private memory backed by huge pages (MAP_HUGETLB),
+    /// if no huge pages are reserved falls back to transparent huge pages.
+    /// check `page_mode()` for the mode which took effect
+    pub fn try_heap_allocate_huge(size: usize) -> Result<Bytes, BytesError> {
+        if size == 0 || size > isize::MAX as usize {
+            return Err(BytesError::InvalidSize(size as u64));
+        }
+        //hugetlb mappings must be a multiple of the huge page size
+        let huge_len = size.next_multiple_of(crate::pages::huge_page_size());
+        if let Ok(mmap) = MmapOptions::new().len(huge_len).huge(None).map_anon() {
+            return Ok(Bytes {
+                bytes: MemType::Anon(mmap),
+                cap: size,
+                page_mode: PageMode::HugeTlb,
+                locked: false,
+                unlink_on_drop: None,
+                owner_lock: None,
+                generation: 0,
+            });
+        }
+        let mmap = MmapOptions::new().len(size).map_anon()?;
+        let mut bytes = Bytes {
+            bytes: MemType::Anon(mmap),
+            cap: size,
+            page_mode: PageMode::Regular,
+            locked: false,
+            unlink_on_drop: None,
+            owner_lock: None,
+            generation: 0,
+        };
+        bytes.advise_transparent_huge_pages(false);
+        Ok(bytes)
+    }
+
+    /// request transparent huge pages for this region with madvise(MADV_HUGEPAGE),
+    /// best done before the memory is first touched. returns the mode now in effect
+    pub fn advise_huge_pages(&mut self) -> PageMode {
+        let shmem = !matches!(self.bytes, MemType::Heap(_, _) | MemType::External(_, _));
+        self.advise_transparent_huge_pages(shmem)
+    }
+
+    /// reserve the blocks of the backing file with fallocate, so that running out of space
+    /// eg on a full tmpfs fails here with NoSpace rather than with SIGBUS on first write
+    pub fn preallocate(&self) -> Result<(), BytesError> {
+        if self.is_read_only() {
+            return Err(BytesError::Unsupported("preallocate"));
+        }
+        let fd = self.fd().ok_or(BytesError::Unsupported("preallocate"))?;
+        crate::pages::preallocate(fd, self.cap)?;
+        Ok(())
+    }
+
+    /// fault in every page writable (readable for read only bytes), so the first accesses
+    /// do not stall on page faults. safe to call while other threads or processes use the region
+    pub fn warm(&self) -> Result<(), BytesError> {
+        let ptr = unsafe { self.bytes.as_ptr() };
+        crate::pages::prefault(ptr, self.cap, !self.is_read_only())?;
+        Ok(())
+    }
+
+    /// mlock the region so it stays resident and is never paged out.
+    /// heap and external memory must be page aligned and a whole number of pages
+    pub fn lock(&mut self) -> Result<(), BytesError> {
+        self.check_whole_pages("lock of partial pages")?;
+        let ptr = unsafe { self.bytes.as_ptr() };
+        crate::pages::lock(ptr, self.cap)?;
+        self.locked = true;
+        Ok(())
+    }
+
+    pub fn unlock(&mut self) -> Result<(), BytesError> {
+        if self.locked {
+            let ptr = unsafe { self.bytes.as_ptr() };
+            crate::pages::unlock(ptr, self.cap)?;
+            self.locked = false;
+        }
+        Ok(())
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    // page granular operations round out to whole pages, which is only safe when the pages
+    // are not shared with other allocations. mappings always own their pages
+    fn check_whole_pages(&self, op: &'static str) -> Result<(), BytesError> {
+        if let MemType::Heap(_, _) | MemType::External(_, _) = self.bytes {
+            let page_size = crate::pages::page_size();
+            let addr = unsafe { self.bytes.as_ptr() } as usize;
+            if !addr.is_multiple_of(page_size) || !self.cap.is_multiple_of(page_size) {
+                return Err(BytesError::Unsupported(op));
+            }
+        }
+        Ok(())
+    }
+
+    /// place the pages of the region according to policy with mbind, pages already faulted
+    /// in are moved where possible. best applied before `warm` or the first write.
+    /// does nothing on a single node machine or a kernel without numa support.
+    /// heap memory must be whole pages, as for `lock`
+    pub fn set_numa_policy(&self, policy: &NumaPolicy) -> Result<(), BytesError> {
+        self.check_whole_pages("numa policy of partial pages")?;
+        if crate::pages::numa_node_count() <= 1 {
+            return Ok(());
+        }
+        let (mode, nodes) = match policy {
+            NumaPolicy::Bind(nodes) => (libc::MPOL_BIND, nodes.as_slice()),
+            NumaPolicy::Interleave(nodes) => (libc::MPOL_INTERLEAVE, nodes.as_slice()),
+            NumaPolicy::Preferred(node) => (libc::MPOL_PREFERRED, std::slice::from_ref(node)),
+        };
+        let ptr = unsafe { self.bytes.as_ptr() };
+        match crate::pages::bind(ptr, self.cap, mode, nodes) {
+            Err(err) if err.raw_os_error() == Some(libc::ENOSYS) => Ok(()),
+            res => Ok(res?),
+        }
+    }
+
+    /// numa node each page of the region is on, None for pages not faulted in yet.
+    /// without kernel numa support all resident memory is reported on node 0
+    pub fn page_nodes(&self) -> Result<Vec<Option<u32>>, BytesError> {
+        let ptr = unsafe { self.bytes.as_ptr() };
+        match crate::pages::page_nodes(ptr, self.cap) {
+            Err(err) if err.raw_os_error() == Some(libc::ENOSYS) => {
+                let pages = self.cap.div_ceil(crate::pages::page_size());
+                Ok(vec![Some(0); pages])
+            }
+            res => Ok(res?),
+        }
+    }
+
+    /// grow the backing file, shm segment or memfd to new_size and remap it.
+    /// requires exclusive access, so no view can outlive a move of the mapping.
+    /// other processes mapping the same segment pick up the new size with `refresh`
+    pub fn grow(&mut self, new_size: usize) -> Result<(), BytesError> {
+        if new_size < self.cap || new_size > isize::MAX as usize {
+            return Err(BytesError::InvalidSize(new_size as u64));
+        }
+        if new_size == self.cap {
+            return Ok(());
+        }
+        match &mut self.bytes {
+            MemType::File(mmap, file) => {
+                file.set_len(new_size as u64)?;
+                unsafe { mmap.remap(new_size, RemapOptions::new().may_move(true)) }?;
+            }
+            MemType::MemFd(mmap, fd) => {
+                //fails with PermissionDenied if the size is sealed
+                let file = File::from(fd.try_clone()?);
+                file.set_len(new_size as u64)?;
+                unsafe { mmap.remap(new_size, RemapOptions::new().may_move(true)) }?;
+            }
+            _ => return Err(BytesError::Unsupported("grow")),
+        }
+        self.cap = new_size;
+        self.generation += 1;
+        Ok(())
+    }
+
+    /// remap the memory if the backing store was grown by another process,
+    /// returns true if the capacity changed
+    pub fn refresh(&mut self) -> Result<bool, BytesError> {
+        let len = match &self.bytes {
+            MemType::File(_, file) | MemType::ReadOnly(_, file) => file.metadata()?.len(),
+            MemType::MemFd(_, fd) => File::from(fd.try_clone()?).metadata()?.len(),
+            _ => return Ok(false),
+        } as usize;
+        if len <= self.cap {
+            return Ok(false);
+        }
+        let options = RemapOptions::new().may_move(true);
+        match &mut self.bytes {
+            MemType::File(mmap, _) | MemType::MemFd(mmap, _) => {
+                unsafe { mmap.remap(len, options) }?
+            }
+            MemType::ReadOnly(mmap, _) => unsafe { mmap.remap(len, options) }?,
+            _ => unreachable!(),
+        }
+        self.cap = len;
+        self.generation += 1;
+        Ok(true)
+    }
+
+    fn advise_transparent_huge_pages(&mut self, shmem: bool) -> PageMode {
+        if self.page_mode == PageMode::Regular {
+            let ptr = unsafe { self.bytes.as_ptr() };
+            if let Ok(true) = crate::pages::advise_huge_pages(ptr, self.cap, shmem) {
+                self.page_mode = PageMode::Transparent;
+            }
+        }
+        self.page_mode
+    }
+}
+impl Drop for Bytes {
+    fn drop(&mut self) {
+        if let Some(segment) = &self.unlink_on_drop {
+            segment.unlink();
+        }
+        match &mut self.bytes {
+            MemType::Heap(ptr, layout) => {
+                //heap pages go back to the allocator, they must not stay locked
+                #[cfg(target_os = "linux")]
+                if self.locked {
+                    let _ = crate::pages::unlock(ptr.as_ptr(), self.cap);
+                }
+                unsafe {
+                    alloc::dealloc(ptr.as_ptr(), *layout);
+                }
+            }
+            MemType::Mapped(_) | MemType::Anon(_) => {
+                //auto dropped
+                //MapMut is owned and will be do it own clean up
+            }
+            MemType::File(_, _) | MemType::ReadOnly(_, _) => {
+                //mapping and file are owned and clean up on drop
+            }
+            #[cfg(target_os = "linux")]
+            MemType::MemFd(_, _) => {
+                //mapping and fd are owned and clean up on drop
+            }
+            MemType::External(ptr, drop_fn) => {
+                #[cfg(target_os = "linux")]
+                if self.locked {
+                    let _ = crate::pages::unlock(ptr.as_ptr(), self.cap);
+                }
+                if let Some(drop_fn) = drop_fn.take() {
+                    drop_fn(ptr.as_ptr(), self.cap);
+                }
+            }
+        }
+    }
+}
+
+pub struct BytesAtomicView<'a> {
+    offset: usize,
+    length: usize,
+    bytes: &'a Bytes,
+}
+
+impl<'a> BytesAtomicView<'a> {
+    pub fn from_bytes(offset: usize, length: usize, bytes: &'a Bytes) -> BytesAtomicView<'a> {
+        assert!(!bytes.is_read_only(), "read only bytes, use BytesReadView");
+        check_view_bounds(offset, length, bytes.capacity());
+        let alignment = align_of::<usize>();
+
+        let ptr = unsafe { bytes.bytes.as_ptr().add(offset) };
+        assert_eq!(ptr.align_offset(alignment), 0, "invalid alignment");
+
+        BytesAtomicView {
+            offset,
+            length,
+            bytes,
+        }
+    }
+
+    /// split into the bytes before offset and the bytes from offset, which do not overlap.
+    /// panics if offset is past the end of the view
+    pub fn split_at(self, offset: usize) -> (ExclusiveView<'a>, ExclusiveView<'a>) {
+        ExclusiveView::new(self).split_at(offset)
+    }
+
+    /// split into non overlapping chunks of size bytes, the bytes after the last whole chunk
+    /// are left in `ChunksExact::into_remainder`. panics if size is 0
+    pub fn chunks_exact(self, size: usize) -> ChunksExact<'a> {
+        ExclusiveView::new(self).chunks_exact(size)
+    }
+}
+
+// accessors of `BytesAtomicView` and `ExclusiveView`, both cover offset..offset + length
+// of bytes
+macro_rules! atomic_view_impl {
+    ($view: ident) => {
+        impl<'a> AsAtomicView for $view<'a> {
+            fn as_atomic_view(&mut self) -> BytesAtomicView<'_> {
+                BytesAtomicView {
+                    offset: self.offset,
+                    length: self.length,
+                    bytes: self.bytes,
+                }
+            }
+        }
+
+        impl<'a> $view<'a> {
+            pub fn sub_slice(&self, range_from: RangeFrom<u32>) -> BytesAtomicView<'a> {
+                let start = range_from.start as usize;
+                check_view_bounds(start, 1, self.length);
+                let new_len = self.length - start;
+                BytesAtomicView {
+                    offset: self.offset + start,
+                    length: new_len,
+                    bytes: self.bytes,
+                }
+            }
+
+            pub fn sub_view(&self, start: u32, length: u32) -> BytesAtomicView<'a> {
+                check_view_bounds(start as usize, length as usize, self.length);
+                BytesAtomicView {
+                    offset: self.offset + start as usize,
+                    length: length as usize,
+                    bytes: self.bytes,
+                }
+            }
+            unsafe fn data_ptr(&self) -> *mut u8 {
+                self.bytes.bytes.as_ptr().add(self.offset)
+            }
+
+            /// flush the region covered by this view, see `Bytes::flush`
+            pub fn flush(&self) -> Result<(), BytesError> {
+                self.bytes.flush_range(self.offset, self.length)
+            }
+
+            /// flush len bytes at offset within this view
+            pub fn flush_range(&self, offset: usize, len: usize) -> Result<(), BytesError> {
+                if offset.checked_add(len).is_none_or(|end| end > self.length) {
+                    return Err(BytesError::OutOfBounds { offset, len });
+                }
+                self.bytes.flush_range(self.offset + offset, len)
+            }
+
+            pub fn flush_async(&self) -> Result<(), BytesError> {
+                self.bytes.flush_async_range(self.offset, self.length)
+            }
+        }
+
+        /// raw byte access. the bytes can be written concurrently through other views, threads or
+        /// processes so they are never handed out as `&[u8]`/`&mut [u8]`, only copied with relaxed
+        /// atomic byte accesses or exposed as `&[AtomicU8]`
+        impl<'a> $view<'a> {
+            pub fn len(&self) -> usize {
+                self.length
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.length == 0
+            }
+
+            pub fn as_ptr(&self) -> *const u8 {
+                unsafe { self.data_ptr() }
+            }
+
+            /// typed accessor of the `Overlay` struct T at offset, see `try_overlay`
+            pub fn overlay<T: Overlay>(&self, offset: usize) -> T::Accessor<'a> {
+                match self.try_overlay::<T>(offset) {
+                    Ok(accessor) => accessor,
+                    Err(err) => access_failed(err),
+                }
+            }
+
+            /// typed accessor of the `Overlay` struct T at offset, the whole struct must be inside
+            /// the view and offset must be aligned for T
+            pub fn try_overlay<T: Overlay>(
+                &self,
+                offset: usize,
+            ) -> Result<T::Accessor<'a>, AccessError> {
+                checked_ptr::<T>(unsafe { self.data_ptr() }, self.length, offset)?;
+                let view = BytesAtomicView {
+                    offset: self.offset + offset,
+                    length: size_of::<T>(),
+                    bytes: self.bytes,
+                };
+                Ok(T::accessor(OverlayView::new(view)))
+            }
+
+            pub fn as_atomic_bytes(&self) -> &'a [AtomicU8] {
+                let ptr = unsafe { self.data_ptr() } as *const AtomicU8;
+                unsafe { std::slice::from_raw_parts(ptr, self.length) }
+            }
+
+            /// copy dst.len() bytes starting at offset into dst
+            pub fn copy_to_slice(&self, offset: usize, dst: &mut [u8]) {
+                let data = unsafe { self.data_ptr() };
+                match checked_range(data, self.length, offset, dst.len()) {
+                    Ok(ptr) => unsafe { atomic_copy_to_slice(ptr, dst) },
+                    Err(err) => access_failed(err),
+                }
+            }
+
+            /// copy src into the view starting at offset
+            pub fn copy_from_slice(&mut self, offset: usize, src: &[u8]) {
+                let data = unsafe { self.data_ptr() };
+                match checked_range(data, self.length, offset, src.len()) {
+                    Ok(ptr) => unsafe { atomic_copy_from_slice(ptr, src) },
+                    Err(err) => access_failed(err),
+                }
+            }
+
+            pub fn to_vec(&self) -> Vec<u8> {
+                let mut vec = vec![0u8; self.length];
+                self.copy_to_slice(0, &mut vec);
+                vec
+            }
         }
-    }
+
+        /// plain (relaxed, no ordering) accessors with an explicit byte order, for encoding
+        /// message bodies. offsets need not be aligned, access outside of the view panics.
+        /// concurrent readers must only read them after an acquire of a counter the writer
+        /// released after writing, eg inside `BroadcastTx::transmit` and
+        /// `BroadcastRx::receive_next`
+        impl<'a> $view<'a> {
+            plain_get_impl!(
+                (u16, get_u16_le, get_u16_be, get_u16_ne),
+                (u32, get_u32_le, get_u32_be, get_u32_ne),
+                (u64, get_u64_le, get_u64_be, get_u64_ne),
+                (i16, get_i16_le, get_i16_be, get_i16_ne),
+                (i32, get_i32_le, get_i32_be, get_i32_ne),
+                (i64, get_i64_le, get_i64_be, get_i64_ne),
+                (f32, get_f32_le, get_f32_be, get_f32_ne),
+                (f64, get_f64_le, get_f64_be, get_f64_ne),
+            );
+
+            plain_put_impl!(
+                (u16, put_u16_le, put_u16_be, put_u16_ne),
+                (u32, put_u32_le, put_u32_be, put_u32_ne),
+                (u64, put_u64_le, put_u64_be, put_u64_ne),
+                (i16, put_i16_le, put_i16_be, put_i16_ne),
+                (i32, put_i32_le, put_i32_be, put_i32_ne),
+                (i64, put_i64_le, put_i64_be, put_i64_ne),
+                (f32, put_f32_le, put_f32_be, put_f32_ne),
+                (f64, put_f64_le, put_f64_be, put_f64_ne),
+            );
+        }
+
+        /// accessors named after the Agrona methods they port, eg getLongVolatile is
+        /// `get_i64_volatile` and putLongOrdered is `put_i64_release`. plain accessors are
+        /// unaligned and native endian, all others must be aligned for the type and are single
+        /// atomic accesses
+        impl<'a> $view<'a> {
+            ordered_access_impl!(
+                (
+                    u8,
+                    get_u8_plain,
+                    put_u8_plain,
+                    get_u8_opaque,
+                    put_u8_opaque,
+                    get_u8_acquire,
+                    put_u8_release,
+                    get_u8_volatile,
+                    put_u8_volatile
+                ),
+                (
+                    i8,
+                    get_i8_plain,
+                    put_i8_plain,
+                    get_i8_opaque,
+                    put_i8_opaque,
+                    get_i8_acquire,
+                    put_i8_release,
+                    get_i8_volatile,
+                    put_i8_volatile
+                ),
+                (
+                    u16,
+                    get_u16_plain,
+                    put_u16_plain,
+                    get_u16_opaque,
+                    put_u16_opaque,
+                    get_u16_acquire,
+                    put_u16_release,
+                    get_u16_volatile,
+                    put_u16_volatile
+                ),
+                (
+                    i16,
+                    get_i16_plain,
+                    put_i16_plain,
+                    get_i16_opaque,
+                    put_i16_opaque,
+                    get_i16_acquire,
+                    put_i16_release,
+                    get_i16_volatile,
+                    put_i16_volatile
+                ),
+                (
+                    u32,
+                    get_u32_plain,
+                    put_u32_plain,
+                    get_u32_opaque,
+                    put_u32_opaque,
+                    get_u32_acquire,
+                    put_u32_release,
+                    get_u32_volatile,
+                    put_u32_volatile
+                ),
+                (
+                    i32,
+                    get_i32_plain,
+                    put_i32_plain,
+                    get_i32_opaque,
+                    put_i32_opaque,
+                    get_i32_acquire,
+                    put_i32_release,
+                    get_i32_volatile,
+                    put_i32_volatile
+                ),
+                (
+                    u64,
+                    get_u64_plain,
+                    put_u64_plain,
+                    get_u64_opaque,
+                    put_u64_opaque,
+                    get_u64_acquire,
+                    put_u64_release,
+                    get_u64_volatile,
+                    put_u64_volatile
+                ),
+                (
+                    i64,
+                    get_i64_plain,
+                    put_i64_plain,
+                    get_i64_opaque,
+                    put_i64_opaque,
+                    get_i64_acquire,
+                    put_i64_release,
+                    get_i64_volatile,
+                    put_i64_volatile
+                ),
+                (
+                    usize,
+                    get_usize_plain,
+                    put_usize_plain,
+                    get_usize_opaque,
+                    put_usize_opaque,
+                    get_usize_acquire,
+                    put_usize_release,
+                    get_usize_volatile,
+                    put_usize_volatile
+                ),
+                (
+                    isize,
+                    get_isize_plain,
+                    put_isize_plain,
+                    get_isize_opaque,
+                    put_isize_opaque,
+                    get_isize_acquire,
+                    put_isize_release,
+                    get_isize_volatile,
+                    put_isize_volatile
+                ),
+                (
+                    f32,
+                    get_f32_plain,
+                    put_f32_plain,
+                    get_f32_opaque,
+                    put_f32_opaque,
+                    get_f32_acquire,
+                    put_f32_release,
+                    get_f32_volatile,
+                    put_f32_volatile
+                ),
+                (
+                    f64,
+                    get_f64_plain,
+                    put_f64_plain,
+                    get_f64_opaque,
+                    put_f64_opaque,
+                    get_f64_acquire,
+                    put_f64_release,
+                    get_f64_volatile,
+                    put_f64_volatile
+                ),
+            );
+        }
+    };
 }
-pub struct Bytes {
-    bytes: MemType,
-    cap: usize,
+
+atomic_view_impl!(BytesAtomicView);
+atomic_view_impl!(ExclusiveView);
+
+/// part of a view made by `split_at` or `chunks_exact`, it does not overlap the other parts
+/// made by the same split. it has the accessors of `BytesAtomicView` but can not be cloned,
+/// views made with `sub_view` or `into_view` are ordinary views. the view which was split
+/// may have been cloned first, so other views of the same bytes can still overlap it
+pub struct ExclusiveView<'a> {
+    offset: usize,
+    length: usize,
+    bytes: &'a Bytes,
 }
 
-impl Bytes {
-    pub fn from_file_backed<P: AsRef<Path>>(file: P, size: u64) -> Self {
-        let file = File::create_new(file).expect("failed to open the file");
-        file.set_len(size).expect("filed to set size");
-        let mmap = unsafe { MmapMut::map_mut(&file) };
-        Self::memap(mmap.unwrap())
+impl<'a> ExclusiveView<'a> {
+    fn new(view: BytesAtomicView<'a>) -> ExclusiveView<'a> {
+        ExclusiveView {
+            offset: view.offset,
+            length: view.length,
+            bytes: view.bytes,
+        }
     }
-}
 
-/// will heap allocate size bytes
-/// ensure memory is zeroed out
-///
-fn heap_allocate(size: usize) -> NonNull<u8> {
-    let new_layout = Layout::array::<u8>(size).unwrap();
-    // Ensure that the new allocation doesn't exceed `isize::MAX` bytes.
-    assert!(
-        new_layout.size() <= isize::MAX as usize,
-        "Allocation too large"
-    );
-    let allocated_ptr = unsafe { alloc::alloc_zeroed(new_layout) };
-    // If allocation fails, `new_ptr` will be null, in which case we abort.
-    match NonNull::new(allocated_ptr) {
-        Some(p) => p,
-        None => alloc::handle_alloc_error(new_layout),
+    pub fn split_at(self, offset: usize) -> (ExclusiveView<'a>, ExclusiveView<'a>) {
+        assert!(
+            offset <= self.length,
+            "split offset={} past the end, length={}",
+            offset,
+            self.length
+        );
+        let head = ExclusiveView {
+            offset: self.offset,
+            length: offset,
+            bytes: self.bytes,
+        };
+        let tail = ExclusiveView {
+            offset: self.offset + offset,
+            length: self.length - offset,
+            bytes: self.bytes,
+        };
+        (head, tail)
     }
-}
-unsafe impl Sync for Bytes {}
-unsafe impl Send for Bytes {}
 
-impl Bytes {
-    pub fn heap_allocate(size: usize) -> Bytes {
-        let ptr = heap_allocate(size);
-        Bytes {
-            bytes: MemType::Heap(ptr),
-            cap: size,
+    pub fn chunks_exact(self, size: usize) -> ChunksExact<'a> {
+        assert!(size != 0, "chunk size must not be 0");
+        let whole = self.length - self.length % size;
+        let (chunks, remainder) = self.split_at(whole);
+        ChunksExact {
+            chunks,
+            remainder,
+            size,
         }
     }
-    pub fn memap(memap: MmapMut) -> Bytes {
-        let cap = memap.len();
-        Bytes {
-            bytes: MemType::Mapped(memap),
-            cap,
+
+    /// ordinary view of the same bytes, which can be cloned
+    pub fn into_view(self) -> BytesAtomicView<'a> {
+        BytesAtomicView {
+            offset: self.offset,
+            length: self.length,
+            bytes: self.bytes,
         }
     }
-    pub fn capacity(&self) -> usize {
-        self.cap
+}
+
+/// iterator over the non overlapping chunks of `chunks_exact`
+pub struct ChunksExact<'a> {
+    // whole chunks not yet returned
+    chunks: ExclusiveView<'a>,
+    remainder: ExclusiveView<'a>,
+    size: usize,
+}
+
+impl<'a> ChunksExact<'a> {
+    /// bytes after the last whole chunk, shorter than the chunk size
+    pub fn into_remainder(self) -> ExclusiveView<'a> {
+        self.remainder
     }
 }
-impl Drop for Bytes {
-    fn drop(&mut self) {
-        match &self.bytes {
-            MemType::Heap(ptr) => {
-                if self.cap > 0 {
-                    let layout = Layout::array::<u8>(self.cap).unwrap();
-                    unsafe {
-                        alloc::dealloc(ptr.as_ptr(), layout);
-                    }
-                }
-            }
-            MemType::Mapped(_) => {
-                //auto dropped
-                //MapMut is owned and will be do it own clean up
-            }
+
+impl<'a> Iterator for ChunksExact<'a> {
+    type Item = ExclusiveView<'a>;
+
+    fn next(&mut self) -> Option<ExclusiveView<'a>> {
+        if self.chunks.length == 0 {
+            return None;
+        }
+        let chunk = ExclusiveView {
+            offset: self.chunks.offset,
+            length: self.size,
+            bytes: self.chunks.bytes,
+        };
+        self.chunks.offset += self.size;
+        self.chunks.length -= self.size;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.chunks.length / self.size;
+        (len, Some(len))
+    }
+}
+
+impl<'a> ExactSizeIterator for ChunksExact<'a> {}
+
+impl<'a> Clone for BytesAtomicView<'a> {
+    fn clone(&self) -> Self {
+        BytesAtomicView {
+            offset: self.offset,
+            length: self.length,
+            bytes: self.bytes,
         }
     }
 }
 
-pub struct BytesAtomicView<'a> {
+/// read only view of a region of `Bytes`, only offers atomic loads.
+/// this is the only view which can be created over read only bytes
+pub struct BytesReadView<'a> {
     offset: usize,
     length: usize,
     bytes: &'a Bytes,
 }
 
-impl<'a> BytesAtomicView<'a> {
-    pub fn from_bytes(offset: usize, length: usize, bytes: &'a Bytes) -> BytesAtomicView<'a> {
-        assert!((offset + length) <= bytes.capacity(), "bounds error");
+impl<'a> BytesReadView<'a> {
+    pub fn from_bytes(offset: usize, length: usize, bytes: &'a Bytes) -> BytesReadView<'a> {
+        check_view_bounds(offset, length, bytes.capacity());
         let alignment = align_of::<usize>();
 
         let ptr = unsafe { bytes.bytes.as_ptr().add(offset) };
         assert_eq!(ptr.align_offset(alignment), 0, "invalid alignment");
 
-        BytesAtomicView {
+        BytesReadView {
             offset,
             length,
             bytes,
         }
     }
 
-    pub fn sub_slice(&self, range_from: RangeFrom<u32>) -> BytesAtomicView<'a> {
+    pub fn sub_slice(&self, range_from: RangeFrom<u32>) -> BytesReadView<'a> {
         let start = range_from.start as usize;
-        assert!(start < self.length);
+        check_view_bounds(start, 1, self.length);
         let new_len = self.length - start;
-        BytesAtomicView {
+        BytesReadView {
             offset: self.offset + start,
             length: new_len,
             bytes: self.bytes,
         }
     }
-    pub fn sub_view(&self, start: u32, length: u32) -> BytesAtomicView<'a> {
-        assert!((start + length) as usize <= self.length);
-        BytesAtomicView {
+    pub fn sub_view(&self, start: u32, length: u32) -> BytesReadView<'a> {
+        check_view_bounds(start as usize, length as usize, self.length);
+        BytesReadView {
             offset: self.offset + start as usize,
             length: length as usize,
             bytes: self.bytes,
         }
     }
-    unsafe fn data_ptr(&self) -> *mut u8 {
+    unsafe fn data_ptr(&self) -> *const u8 {
         self.bytes.bytes.as_ptr().add(self.offset)
     }
+
+    // atomic at offset, only ever used for loads as the memory may be mapped PROT_READ
+    fn try_atomic_ref<A>(&self, offset: usize) -> Result<&'a A, AccessError> {
+        let ptr = checked_ptr::<A>(unsafe { self.data_ptr() }, self.length, offset)?;
+        Ok(unsafe { &*(ptr as *const A) })
+    }
+
+    #[inline]
+    fn atomic_ref<A>(&self, offset: usize) -> &'a A {
+        match self.try_atomic_ref(offset) {
+            Ok(atomic) => atomic,
+            Err(err) => access_failed(err),
+        }
+    }
+
+    unsafe fn atomic_ref_unchecked<A>(&self, offset: usize) -> &'a A {
+        debug_assert!(checked_ptr::<A>(self.data_ptr(), self.length, offset).is_ok());
+        &*(self.data_ptr().add(offset) as *const A)
+    }
+}
+
+/// plain getters, see the plain accessors of `BytesAtomicView`
+impl<'a> BytesReadView<'a> {
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        unsafe { self.data_ptr() }
+    }
+
+    /// copy dst.len() bytes starting at offset into dst
+    pub fn copy_to_slice(&self, offset: usize, dst: &mut [u8]) {
+        let data = unsafe { self.data_ptr() };
+        match checked_range(data, self.length, offset, dst.len()) {
+            Ok(ptr) => unsafe { atomic_copy_to_slice(ptr, dst) },
+            Err(err) => access_failed(err),
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut vec = vec![0u8; self.length];
+        self.copy_to_slice(0, &mut vec);
+        vec
+    }
+
+    plain_get_impl!(
+        (u16, get_u16_le, get_u16_be, get_u16_ne),
+        (u32, get_u32_le, get_u32_be, get_u32_ne),
+        (u64, get_u64_le, get_u64_be, get_u64_ne),
+        (i16, get_i16_le, get_i16_be, get_i16_ne),
+        (i32, get_i32_le, get_i32_be, get_i32_ne),
+        (i64, get_i64_le, get_i64_be, get_i64_ne),
+        (f32, get_f32_le, get_f32_be, get_f32_ne),
+        (f64, get_f64_le, get_f64_be, get_f64_ne),
+    );
+}
+
+impl<'a> Clone for BytesReadView<'a> {
+    fn clone(&self) -> Self {
+        BytesReadView {
+            offset: self.offset,
+            length: self.length,
+            bytes: self.bytes,
+        }
+    }
+}
+
+impl<'a> From<BytesAtomicView<'a>> for BytesReadView<'a> {
+    fn from(view: BytesAtomicView<'a>) -> Self {
+        BytesReadView {
+            offset: view.offset,
+            length: view.length,
+            bytes: view.bytes,
+        }
+    }
+}
+
+impl<'a> From<ExclusiveView<'a>> for BytesReadView<'a> {
+    fn from(view: ExclusiveView<'a>) -> Self {
+        BytesReadView::from(&view)
+    }
+}
+
+// reading does not take away the exclusive view's write access
+impl<'a> From<&ExclusiveView<'a>> for BytesReadView<'a> {
+    fn from(view: &ExclusiveView<'a>) -> Self {
+        BytesReadView {
+            offset: view.offset,
+            length: view.length,
+            bytes: view.bytes,
+        }
+    }
+}
+
+/// `BytesAtomicView` holding a reference count on the bytes, it is 'static so can be
+/// moved to long lived threads or tasks which outlive the scope the bytes were created in
+#[derive(Clone)]
+pub struct OwnedAtomicView {
+    offset: usize,
+    length: usize,
+    bytes: Arc<Bytes>,
+}
+
+impl OwnedAtomicView {
+    pub fn from_bytes(offset: usize, length: usize, bytes: Arc<Bytes>) -> OwnedAtomicView {
+        //same checks as a borrowed view
+        BytesAtomicView::from_bytes(offset, length, &bytes);
+        OwnedAtomicView {
+            offset,
+            length,
+            bytes,
+        }
+    }
+
+    pub fn view(&self) -> BytesAtomicView<'_> {
+        BytesAtomicView {
+            offset: self.offset,
+            length: self.length,
+            bytes: &self.bytes,
+        }
+    }
+
+    pub fn bytes(&self) -> &Arc<Bytes> {
+        &self.bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// view which is not tied to the lifetime of self,
+    /// the caller must keep a clone of the Arc alive for as long as the view is used
+    pub(crate) unsafe fn view_unbound(&self) -> BytesAtomicView<'static> {
+        BytesAtomicView {
+            offset: self.offset,
+            length: self.length,
+            bytes: &*Arc::as_ptr(&self.bytes),
+        }
+    }
+}
+
+/// `BytesReadView` holding a reference count on the bytes
+#[derive(Clone)]
+pub struct OwnedReadView {
+    offset: usize,
+    length: usize,
+    bytes: Arc<Bytes>,
+}
+
+impl OwnedReadView {
+    pub fn from_bytes(offset: usize, length: usize, bytes: Arc<Bytes>) -> OwnedReadView {
+        BytesReadView::from_bytes(offset, length, &bytes);
+        OwnedReadView {
+            offset,
+            length,
+            bytes,
+        }
+    }
+
+    pub fn view(&self) -> BytesReadView<'_> {
+        BytesReadView {
+            offset: self.offset,
+            length: self.length,
+            bytes: &self.bytes,
+        }
+    }
+
+    pub fn bytes(&self) -> &Arc<Bytes> {
+        &self.bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// see `OwnedAtomicView::view_unbound`
+    pub(crate) unsafe fn view_unbound(&self) -> BytesReadView<'static> {
+        BytesReadView {
+            offset: self.offset,
+            length: self.length,
+            bytes: &*Arc::as_ptr(&self.bytes),
+        }
+    }
+}
+
+impl From<OwnedAtomicView> for OwnedReadView {
+    fn from(view: OwnedAtomicView) -> Self {
+        OwnedReadView {
+            offset: view.offset,
+            length: view.length,
+            bytes: view.bytes,
+        }
+    }
+}
+
+pub trait AtomicRefCell<'a, T> {
+    /// return a reference to an atomic view of type T
+    /// index must be align_of<T>, panics if T is not inside the view or is misaligned
+    #[inline]
+    fn get_atomic(&'a self, index: usize) -> &'a T {
+        match self.try_get_atomic(index) {
+            Ok(atomic) => atomic,
+            Err(err) => access_failed(err),
+        }
+    }
+
+    fn try_get_atomic(&'a self, index: usize) -> Result<&'a T, AccessError>;
+
+    /// # Safety
+    /// index + size_of<T> must be inside the view and index must be aligned for T
+    unsafe fn get_atomic_unchecked(&'a self, index: usize) -> &'a T;
+}
+
+/// atomic load and store at offset, panics if the value is not inside the view or is
+/// misaligned. the try_ variants return the error instead and the unchecked variants are
+/// for hot paths where the offset is already known to be valid
+pub trait LoadStore<T> {
+    fn load_at(&self, offset: usize, ordering: Ordering) -> T;
+    fn store_at(&mut self, offset: usize, val: T, ordering: Ordering);
+    fn try_load_at(&self, offset: usize, ordering: Ordering) -> Result<T, AccessError>;
+    fn try_store_at(
+        &mut self,
+        offset: usize,
+        val: T,
+        ordering: Ordering,
+    ) -> Result<(), AccessError>;
+    /// # Safety
+    /// offset + size_of<T> must be inside the view and offset must be aligned for T
+    unsafe fn load_at_unchecked(&self, offset: usize, ordering: Ordering) -> T;
+    /// # Safety
+    /// see `load_at_unchecked`
+    unsafe fn store_at_unchecked(&mut self, offset: usize, val: T, ordering: Ordering);
+}
+
+/// atomic read-modify-write at offset, same semantics as the methods of the std atomic
+/// types eg Agrona's getAndAddLong is `fetch_add_at` and compareAndSetLong is `compare_exchange_at`
+pub trait AtomicRmw<T> {
+    fn fetch_add_at(&self, offset: usize, val: T, ordering: Ordering) -> T;
+    fn fetch_sub_at(&self, offset: usize, val: T, ordering: Ordering) -> T;
+    fn swap_at(&self, offset: usize, val: T, ordering: Ordering) -> T;
+    fn compare_exchange_at(
+        &self,
+        offset: usize,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>;
+    /// may fail spuriously, use in a loop
+    fn compare_exchange_weak_at(
+        &self,
+        offset: usize,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>;
+    fn fetch_or_at(&self, offset: usize, val: T, ordering: Ordering) -> T;
+    fn fetch_and_at(&self, offset: usize, val: T, ordering: Ordering) -> T;
+    fn fetch_xor_at(&self, offset: usize, val: T, ordering: Ordering) -> T;
+    fn fetch_max_at(&self, offset: usize, val: T, ordering: Ordering) -> T;
+    fn fetch_min_at(&self, offset: usize, val: T, ordering: Ordering) -> T;
+}
+
+/// atomic loads offered by read only views
+pub trait AtomicLoad<T> {
+    fn load_at(&self, offset: usize, ordering: Ordering) -> T;
+    fn try_load_at(&self, offset: usize, ordering: Ordering) -> Result<T, AccessError>;
+    /// # Safety
+    /// offset + size_of<T> must be inside the view and offset must be aligned for T
+    unsafe fn load_at_unchecked(&self, offset: usize, ordering: Ordering) -> T;
+}
+
+macro_rules! atomic_load_impl {
+    ($type: ty, $atomic_ty: ty) => {
+        impl<'a> AtomicLoad<$type> for BytesReadView<'a> {
+            fn load_at(&self, offset: usize, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.atomic_ref(offset);
+                atomic.load(ordering)
+            }
+
+            fn try_load_at(&self, offset: usize, ordering: Ordering) -> Result<$type, AccessError> {
+                let atomic: &$atomic_ty = self.try_atomic_ref(offset)?;
+                Ok(atomic.load(ordering))
+            }
+
+            unsafe fn load_at_unchecked(&self, offset: usize, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.atomic_ref_unchecked(offset);
+                atomic.load(ordering)
+            }
+        }
+    };
+}
+
+// ordering of the load of a read-modify-write loop, a load cannot be Release
+fn load_ordering(ordering: Ordering) -> Ordering {
+    match ordering {
+        Ordering::Release => Ordering::Relaxed,
+        Ordering::AcqRel => Ordering::Acquire,
+        ordering => ordering,
+    }
 }
 
-impl<'a> Deref for BytesAtomicView<'a> {
-    type Target = [u8];
-    fn deref(&self) -> &[u8] {
-        unsafe { std::slice::from_raw_parts(self.data_ptr(), self.length) }
-    }
-}
+// floats are stored as their bit pattern in the atomic integer of the same width.
+// compare exchange compares bit patterns, so -0.0 != 0.0 and NaN can match itself.
+// add, sub, max and min are compare exchange loops, or, and, xor act on the bits
+macro_rules! float_impl {
+    ($type: ty, $atomic_ty: ty, $fetch_update: ident) => {
+        float_impl!(@view BytesAtomicView, $type, $atomic_ty, $fetch_update);
+        float_impl!(@view ExclusiveView, $type, $atomic_ty, $fetch_update);
+
+        impl<'a> AtomicLoad<$type> for BytesReadView<'a> {
+            fn load_at(&self, offset: usize, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.atomic_ref(offset);
+                <$type>::from_bits(atomic.load(ordering))
+            }
+
+            fn try_load_at(&self, offset: usize, ordering: Ordering) -> Result<$type, AccessError> {
+                let atomic: &$atomic_ty = self.try_atomic_ref(offset)?;
+                Ok(<$type>::from_bits(atomic.load(ordering)))
+            }
+
+            unsafe fn load_at_unchecked(&self, offset: usize, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.atomic_ref_unchecked(offset);
+                <$type>::from_bits(atomic.load(ordering))
+            }
+        }
+
+        // compare exchange loop applying f to the value of atomic, returns the previous value
+        fn $fetch_update(
+            atomic: &$atomic_ty,
+            ordering: Ordering,
+            f: impl Fn($type) -> $type,
+        ) -> $type {
+            let update = |bits| Some(f(<$type>::from_bits(bits)).to_bits());
+            match atomic.fetch_update(ordering, load_ordering(ordering), update) {
+                Ok(bits) | Err(bits) => <$type>::from_bits(bits),
+            }
+        }
+
+    };
+    (@view $view: ident, $type: ty, $atomic_ty: ty, $fetch_update: ident) => {
+        impl<'a> LoadStore<$type> for $view<'a> {
+            fn load_at(&self, offset: usize, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                <$type>::from_bits(atomic.load(ordering))
+            }
+
+            fn store_at(&mut self, offset: usize, val: $type, ordering: Ordering) {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                atomic.store(val.to_bits(), ordering)
+            }
+
+            fn try_load_at(&self, offset: usize, ordering: Ordering) -> Result<$type, AccessError> {
+                let atomic: &$atomic_ty = self.try_get_atomic(offset)?;
+                Ok(<$type>::from_bits(atomic.load(ordering)))
+            }
+
+            fn try_store_at(
+                &mut self,
+                offset: usize,
+                val: $type,
+                ordering: Ordering,
+            ) -> Result<(), AccessError> {
+                let atomic: &$atomic_ty = self.try_get_atomic(offset)?;
+                atomic.store(val.to_bits(), ordering);
+                Ok(())
+            }
+
+            unsafe fn load_at_unchecked(&self, offset: usize, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.get_atomic_unchecked(offset);
+                <$type>::from_bits(atomic.load(ordering))
+            }
+
+            unsafe fn store_at_unchecked(&mut self, offset: usize, val: $type, ordering: Ordering) {
+                let atomic: &$atomic_ty = self.get_atomic_unchecked(offset);
+                atomic.store(val.to_bits(), ordering)
+            }
+        }
+
+        impl<'a> AtomicRmw<$type> for $view<'a> {
+            fn fetch_add_at(&self, offset: usize, val: $type, ordering: Ordering) -> $type {
+                $fetch_update(self.get_atomic(offset), ordering, |current| current + val)
+            }
+
+            fn fetch_sub_at(&self, offset: usize, val: $type, ordering: Ordering) -> $type {
+                $fetch_update(self.get_atomic(offset), ordering, |current| current - val)
+            }
+
+            fn swap_at(&self, offset: usize, val: $type, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                <$type>::from_bits(atomic.swap(val.to_bits(), ordering))
+            }
+
+            fn compare_exchange_at(
+                &self,
+                offset: usize,
+                current: $type,
+                new: $type,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$type, $type> {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                atomic
+                    .compare_exchange(current.to_bits(), new.to_bits(), success, failure)
+                    .map(<$type>::from_bits)
+                    .map_err(<$type>::from_bits)
+            }
+
+            fn compare_exchange_weak_at(
+                &self,
+                offset: usize,
+                current: $type,
+                new: $type,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$type, $type> {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                atomic
+                    .compare_exchange_weak(current.to_bits(), new.to_bits(), success, failure)
+                    .map(<$type>::from_bits)
+                    .map_err(<$type>::from_bits)
+            }
 
-impl<'a> DerefMut for BytesAtomicView<'a> {
-    fn deref_mut(&mut self) -> &mut [u8] {
-        unsafe { std::slice::from_raw_parts_mut(self.data_ptr(), self.length) }
-    }
-}
+            fn fetch_or_at(&self, offset: usize, val: $type, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                <$type>::from_bits(atomic.fetch_or(val.to_bits(), ordering))
+            }
 
-impl<'a> Clone for BytesAtomicView<'a> {
-    fn clone(&self) -> Self {
-        BytesAtomicView {
-            offset: self.offset,
-            length: self.length,
-            bytes: self.bytes,
-        }
-    }
-}
+            fn fetch_and_at(&self, offset: usize, val: $type, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                <$type>::from_bits(atomic.fetch_and(val.to_bits(), ordering))
+            }
 
-pub trait AtomicRefCell<'a, T> {
-    /// return a reference to an atomic view of type T
-    /// index must be align_of<T>
-    fn get_atomic(&'a self, index: usize) -> &'a T;
-}
+            fn fetch_xor_at(&self, offset: usize, val: $type, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                <$type>::from_bits(atomic.fetch_xor(val.to_bits(), ordering))
+            }
 
-pub trait LoadStore<T> {
-    fn load_at(&self, offset: usize, ordering: Ordering) -> T;
-    fn store_at(&mut self, offset: usize, val: T, ordering: Ordering);
+            fn fetch_max_at(&self, offset: usize, val: $type, ordering: Ordering) -> $type {
+                $fetch_update(self.get_atomic(offset), ordering, |current| {
+                    current.max(val)
+                })
+            }
+
+            fn fetch_min_at(&self, offset: usize, val: $type, ordering: Ordering) -> $type {
+                $fetch_update(self.get_atomic(offset), ordering, |current| {
+                    current.min(val)
+                })
+            }
+        }
+    };
 }
 
 macro_rules! load_store_impl {
     ($type: ty, $atomic_ty: ty) => {
-        impl<'a> LoadStore<$type> for BytesAtomicView<'a> {
+        load_store_impl!(@view BytesAtomicView, $type, $atomic_ty);
+        load_store_impl!(@view ExclusiveView, $type, $atomic_ty);
+    };
+    (@view $view: ident, $type: ty, $atomic_ty: ty) => {
+        impl<'a> LoadStore<$type> for $view<'a> {
             fn load_at(&self, offset: usize, ordering: Ordering) -> $type {
                 let atomic: &$atomic_ty = self.get_atomic(offset);
                 atomic.load(ordering)
@@ -185,25 +2276,105 @@ macro_rules! load_store_impl {
                 let atomic: &$atomic_ty = self.get_atomic(offset);
                 atomic.store(val, ordering)
             }
+
+            fn try_load_at(&self, offset: usize, ordering: Ordering) -> Result<$type, AccessError> {
+                let atomic: &$atomic_ty = self.try_get_atomic(offset)?;
+                Ok(atomic.load(ordering))
+            }
+
+            fn try_store_at(
+                &mut self,
+                offset: usize,
+                val: $type,
+                ordering: Ordering,
+            ) -> Result<(), AccessError> {
+                let atomic: &$atomic_ty = self.try_get_atomic(offset)?;
+                atomic.store(val, ordering);
+                Ok(())
+            }
+
+            unsafe fn load_at_unchecked(&self, offset: usize, ordering: Ordering) -> $type {
+                let atomic: &$atomic_ty = self.get_atomic_unchecked(offset);
+                atomic.load(ordering)
+            }
+
+            unsafe fn store_at_unchecked(&mut self, offset: usize, val: $type, ordering: Ordering) {
+                let atomic: &$atomic_ty = self.get_atomic_unchecked(offset);
+                atomic.store(val, ordering)
+            }
+        }
+    };
+}
+macro_rules! atomic_rmw_impl {
+    (@view $view: ident, $type: ty, $atomic_ty: ty, $($method: ident => $fetch: ident),*) => {
+        impl<'a> AtomicRmw<$type> for $view<'a> {
+            $(
+                fn $method(&self, offset: usize, val: $type, ordering: Ordering) -> $type {
+                    let atomic: &$atomic_ty = self.get_atomic(offset);
+                    atomic.$fetch(val, ordering)
+                }
+            )*
+
+            fn compare_exchange_at(
+                &self,
+                offset: usize,
+                current: $type,
+                new: $type,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$type, $type> {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                atomic.compare_exchange(current, new, success, failure)
+            }
+
+            fn compare_exchange_weak_at(
+                &self,
+                offset: usize,
+                current: $type,
+                new: $type,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$type, $type> {
+                let atomic: &$atomic_ty = self.get_atomic(offset);
+                atomic.compare_exchange_weak(current, new, success, failure)
+            }
         }
     };
+    (@view $view: ident, $type: ty, $atomic_ty: ty) => {
+        atomic_rmw_impl!(@view $view, $type, $atomic_ty,
+            fetch_add_at => fetch_add,
+            fetch_sub_at => fetch_sub,
+            swap_at => swap,
+            fetch_or_at => fetch_or,
+            fetch_and_at => fetch_and,
+            fetch_xor_at => fetch_xor,
+            fetch_max_at => fetch_max,
+            fetch_min_at => fetch_min
+        );
+    };
+    ($type: ty, $atomic_ty: ty) => {
+        atomic_rmw_impl!(@view BytesAtomicView, $type, $atomic_ty);
+        atomic_rmw_impl!(@view ExclusiveView, $type, $atomic_ty);
+    };
 }
 macro_rules! atomic_ref_impl {
     ($type: ty, $atomic_ty: ty) => {
-        impl<'a> AtomicRefCell<'a, $atomic_ty> for BytesAtomicView<'a> {
-            fn get_atomic(&'a self, offset: usize) -> &'a $atomic_ty {
-                debug_assert!(offset < self.length, "bounds error");
-                let atomic = unsafe {
-                    let ptr = self.data_ptr().add(offset) as *mut $type;
-                    let expected_alignment = align_of::<$atomic_ty>();
-                    let is_aligned = ptr.align_offset(expected_alignment) == 0;
-                    if !is_aligned {
-                        let err = format!("invalid alignment offset={}, Atomic type", offset);
-                        debug_assert!(is_aligned, "{}", err);
-                    }
-                    <$atomic_ty>::from_ptr(ptr)
-                };
-                atomic
+        atomic_ref_impl!(@view BytesAtomicView, $type, $atomic_ty);
+        atomic_ref_impl!(@view ExclusiveView, $type, $atomic_ty);
+    };
+    (@view $view: ident, $type: ty, $atomic_ty: ty) => {
+        impl<'a> AtomicRefCell<'a, $atomic_ty> for $view<'a> {
+            fn try_get_atomic(&'a self, offset: usize) -> Result<&'a $atomic_ty, AccessError> {
+                let ptr =
+                    checked_ptr::<$atomic_ty>(unsafe { self.data_ptr() }, self.length, offset)?;
+                Ok(unsafe { <$atomic_ty>::from_ptr(ptr as *mut $type) })
+            }
+
+            unsafe fn get_atomic_unchecked(&'a self, offset: usize) -> &'a $atomic_ty {
+                debug_assert!(
+                    checked_ptr::<$atomic_ty>(self.data_ptr(), self.length, offset).is_ok()
+                );
+                <$atomic_ty>::from_ptr(self.data_ptr().add(offset) as *mut $type)
             }
         }
     };
@@ -220,6 +2391,9 @@ atomic_ref_impl!(i16, AtomicI16);
 atomic_ref_impl!(i32, AtomicI32);
 atomic_ref_impl!(i64, AtomicI64);
 
+atomic_ref_impl!(usize, AtomicUsize);
+atomic_ref_impl!(isize, AtomicIsize);
+
 load_store_impl!(u64, AtomicU64);
 load_store_impl!(u32, AtomicU32);
 load_store_impl!(u16, AtomicU16);
@@ -230,12 +2404,541 @@ load_store_impl!(i32, AtomicI32);
 load_store_impl!(i16, AtomicI16);
 load_store_impl!(i8, AtomicI8);
 
+load_store_impl!(usize, AtomicUsize);
+load_store_impl!(isize, AtomicIsize);
+load_store_impl!(bool, AtomicBool);
+
+atomic_rmw_impl!(u64, AtomicU64);
+atomic_rmw_impl!(u32, AtomicU32);
+atomic_rmw_impl!(u16, AtomicU16);
+atomic_rmw_impl!(u8, AtomicU8);
+
+atomic_rmw_impl!(i64, AtomicI64);
+atomic_rmw_impl!(i32, AtomicI32);
+atomic_rmw_impl!(i16, AtomicI16);
+atomic_rmw_impl!(i8, AtomicI8);
+
+atomic_rmw_impl!(usize, AtomicUsize);
+atomic_rmw_impl!(isize, AtomicIsize);
+
+atomic_load_impl!(u64, AtomicU64);
+atomic_load_impl!(u32, AtomicU32);
+atomic_load_impl!(u16, AtomicU16);
+atomic_load_impl!(u8, AtomicU8);
+
+atomic_load_impl!(i64, AtomicI64);
+atomic_load_impl!(i32, AtomicI32);
+atomic_load_impl!(i16, AtomicI16);
+atomic_load_impl!(i8, AtomicI8);
+
+atomic_load_impl!(usize, AtomicUsize);
+atomic_load_impl!(isize, AtomicIsize);
+atomic_load_impl!(bool, AtomicBool);
+
+float_impl!(f64, AtomicU64, fetch_update_f64);
+float_impl!(f32, AtomicU32, fetch_update_f32);
+
 #[cfg(test)]
 mod tests {
 
-    use crate::bytes::{AtomicRefCell, Bytes, BytesAtomicView, LoadStore};
+    use crate::bytes::{
+        copy_between_views, AccessError, AtomicLoad, AtomicRefCell, AtomicRmw, Bytes,
+        BytesAtomicView, BytesError, BytesReadView, LoadStore, NumaPolicy, OwnedAtomicView,
+        OwnedReadView, PageMode, CACHE_LINE_SIZE,
+    };
+    use std::fs;
     use std::sync::atomic::Ordering::Relaxed;
-    use std::sync::atomic::{AtomicU16, AtomicU32, AtomicU64, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn shm_path(name: &str) -> String {
+        format!("/dev/shm/crossbytes.test.{}.{}", name, std::process::id())
+    }
+
+    #[test]
+    fn test_try_heap_allocate_zero_size() {
+        let res = Bytes::try_heap_allocate(0);
+        assert!(matches!(res, Err(BytesError::InvalidSize(0))));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_try_from_file_backed_already_exists() {
+        let path = shm_path("exists");
+        let _ = fs::remove_file(&path);
+        let bytes = Bytes::try_from_file_backed(&path, 64).unwrap();
+        assert_eq!(64, bytes.capacity());
+        let res = Bytes::try_from_file_backed(&path, 64);
+        assert!(matches!(res, Err(BytesError::AlreadyExists)));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_try_from_file_backed_not_found() {
+        let res = Bytes::try_from_file_backed("/dev/shm/no/such/dir/segment", 64);
+        assert!(matches!(res, Err(BytesError::NotFound)));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_open_file_backed_shares_memory() {
+        let path = shm_path("open");
+        let _ = fs::remove_file(&path);
+        let created = Bytes::from_file_backed(&path, 64);
+        let opened = Bytes::open_file_backed(&path);
+        assert_eq!(64, opened.capacity());
+
+        let mut tx_view = BytesAtomicView::from_bytes(0, 64, &created);
+        let rx_view = BytesAtomicView::from_bytes(0, 64, &opened);
+        tx_view.store_at(8, 42u64, Ordering::Release);
+        assert_eq!(42u64, rx_view.load_at(8, Ordering::Acquire));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_open_file_backed_size_mismatch() {
+        let path = shm_path("mismatch");
+        let _ = fs::remove_file(&path);
+        let _created = Bytes::from_file_backed(&path, 64);
+        let res = Bytes::try_open_file_backed(&path, Some(128));
+        assert!(matches!(
+            res,
+            Err(BytesError::SizeMismatch {
+                expected: 128,
+                actual: 64
+            })
+        ));
+        assert!(Bytes::try_open_file_backed(&path, Some(64)).is_ok());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_open_file_backed_not_found() {
+        let res = Bytes::try_open_file_backed(shm_path("missing"), None);
+        assert!(matches!(res, Err(BytesError::NotFound)));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_shm_create_open_unlink() {
+        let name = format!("crossbytes.test.shm.{}", std::process::id());
+        let _ = Bytes::shm_unlink(&name);
+        let created = Bytes::shm_create(&name, 64);
+        assert!(matches!(
+            Bytes::try_shm_create(&name, 64),
+            Err(BytesError::AlreadyExists)
+        ));
+        let opened = Bytes::try_shm_open(&format!("/{}", name), Some(64)).unwrap();
+        //same segment as the jvm would see under /dev/shm
+        let by_path = Bytes::open_file_backed(crate::shm::path(&name).unwrap());
+
+        let mut view = BytesAtomicView::from_bytes(0, 64, &created);
+        view.store_at(0, 11u64, Ordering::Release);
+        let opened_view = BytesAtomicView::from_bytes(0, 64, &opened);
+        let path_view = BytesAtomicView::from_bytes(0, 64, &by_path);
+        assert_eq!(11u64, opened_view.load_at(0, Ordering::Acquire));
+        assert_eq!(11u64, path_view.load_at(0, Ordering::Acquire));
+
+        Bytes::shm_unlink(&name).unwrap();
+        assert!(matches!(
+            Bytes::try_shm_open(&name, None),
+            Err(BytesError::NotFound)
+        ));
+        //existing mappings remain valid after unlink
+        assert_eq!(11u64, opened_view.load_at(0, Ordering::Acquire));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_heap_allocate_huge() {
+        let bytes = Bytes::try_heap_allocate_huge(4096).unwrap();
+        assert_eq!(4096, bytes.capacity());
+        let mut view = BytesAtomicView::from_bytes(0, 4096, &bytes);
+        assert_eq!(0u64, view.load_at(4088, Ordering::Relaxed));
+        view.store_at(4088, 5u64, Ordering::Relaxed);
+        assert_eq!(5u64, view.load_at(4088, Ordering::Relaxed));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_advise_huge_pages() {
+        let mut heap = Bytes::heap_allocate(4 * 1024 * 1024);
+        assert_eq!(PageMode::Regular, heap.page_mode());
+        let mode = heap.advise_huge_pages();
+        assert_ne!(PageMode::HugeTlb, mode);
+        assert_eq!(mode, heap.page_mode());
+
+        let path = shm_path("huge");
+        let _ = fs::remove_file(&path);
+        let mut mapped = Bytes::from_file_backed(&path, 4 * 1024 * 1024);
+        assert_eq!(PageMode::Regular, mapped.page_mode());
+        assert_eq!(mapped.advise_huge_pages(), mapped.page_mode());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_preallocate_warm_and_lock() {
+        let path = shm_path("warm");
+        let _ = fs::remove_file(&path);
+        let mut bytes = Bytes::from_file_backed(&path, 64 * 1024);
+        bytes.preallocate().unwrap();
+        bytes.warm().unwrap();
+        assert!(!bytes.is_locked());
+        bytes.lock().unwrap();
+        assert!(bytes.is_locked());
+        bytes.unlock().unwrap();
+        assert!(!bytes.is_locked());
+        let view = BytesAtomicView::from_bytes(0, 64 * 1024, &bytes);
+        assert_eq!(0u64, view.load_at(64 * 1024 - 8, Ordering::Relaxed));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_warm_and_lock_heap() {
+        let mut bytes = Bytes::heap_allocate(10_000);
+        bytes.warm().unwrap();
+        //the first and last page are shared with other allocations
+        assert!(matches!(bytes.lock(), Err(BytesError::Unsupported(_))));
+        let mut bytes = Bytes::heap_allocate_aligned(3 * 4096, 4096);
+        bytes.lock().unwrap();
+        assert!(bytes.is_locked());
+        assert!(matches!(
+            bytes.preallocate(),
+            Err(BytesError::Unsupported(_))
+        ));
+        let view = BytesAtomicView::from_bytes(0, 10_000, &bytes);
+        assert!(view.to_vec().iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_heap_allocate_alignment() {
+        let bytes = Bytes::heap_allocate(24);
+        assert_eq!(CACHE_LINE_SIZE, bytes.alignment());
+        let view = BytesAtomicView::from_bytes(0, 24, &bytes);
+        assert_eq!(0, view.as_ptr().align_offset(CACHE_LINE_SIZE));
+
+        let bytes = Bytes::heap_allocate_aligned(100, 4096);
+        assert_eq!(4096, bytes.alignment());
+        let view = BytesAtomicView::from_bytes(0, 100, &bytes);
+        assert_eq!(0, view.as_ptr().align_offset(4096));
+    }
+
+    #[test]
+    fn test_heap_allocate_invalid_alignment() {
+        assert!(matches!(
+            Bytes::try_heap_allocate_aligned(64, 48),
+            Err(BytesError::InvalidAlignment(48))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_bytes_from_vec() {
+        //the system allocator returns at least 16 byte aligned blocks
+        let mut vec = vec![0u8; 512];
+        vec[8] = 42;
+        let bytes = Bytes::from_vec(vec);
+        assert_eq!(512, bytes.capacity());
+        assert!(bytes.alignment() >= 8);
+        let mut view = BytesAtomicView::from_bytes(0, 512, &bytes);
+        assert_eq!(42u64, view.load_at(8, Relaxed));
+        view.store_at(504, 7u64, Relaxed);
+        assert!(matches!(bytes.flush(), Err(BytesError::Unsupported(_))));
+
+        let bytes = Bytes::from_boxed_slice(vec![1u8; 64].into_boxed_slice());
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        assert_eq!(0x0101_0101u32, view.load_at(60, Relaxed));
+        match Bytes::try_from_vec(Vec::with_capacity(16)) {
+            Err((BytesError::InvalidSize(0), vec)) => assert!(vec.capacity() >= 16),
+            _ => panic!("empty vec accepted"),
+        }
+        match Bytes::try_from_boxed_slice(Box::new([])) {
+            Err((BytesError::InvalidSize(0), boxed)) => assert!(boxed.is_empty()),
+            _ => panic!("empty boxed slice accepted"),
+        }
+    }
+
+    #[test]
+    fn test_bytes_from_raw_parts() {
+        use std::sync::atomic::AtomicUsize;
+        static RELEASED: AtomicUsize = AtomicUsize::new(0);
+        let layout = std::alloc::Layout::from_size_align(256, 64).unwrap();
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        let bytes = unsafe {
+            Bytes::from_raw_parts(ptr, 256, move |ptr, len| {
+                RELEASED.store(len, Ordering::Relaxed);
+                std::alloc::dealloc(ptr, layout);
+            })
+        }
+        .unwrap();
+        assert!(bytes.alignment() >= 64);
+        let mut view = BytesAtomicView::from_bytes(0, 256, &bytes);
+        view.store_at(0, 1u32, Relaxed);
+        drop(bytes);
+        assert_eq!(256, RELEASED.load(Ordering::Relaxed));
+
+        let unaligned = unsafe { Bytes::from_raw_parts(ptr.wrapping_add(1), 8, |_, _| {}) };
+        assert!(matches!(unaligned, Err(BytesError::Misaligned(addr)) if addr == ptr as usize + 1));
+        let null = unsafe { Bytes::from_raw_parts(std::ptr::null_mut(), 8, |_, _| {}) };
+        assert!(matches!(null, Err(BytesError::NullPointer)));
+    }
+
+    #[test]
+    fn test_owned_views_across_threads() {
+        let bytes = Arc::new(Bytes::heap_allocate(64));
+        let view = OwnedAtomicView::from_bytes(0, 64, bytes.clone());
+        let read_view: OwnedReadView = view.clone().into();
+        let writer = std::thread::spawn(move || {
+            view.view().store_at(8, 11u64, Ordering::Release);
+        });
+        writer.join().unwrap();
+        let reader = std::thread::spawn(move || read_view.view().load_at(8, Ordering::Acquire));
+        assert_eq!(11u64, reader.join().unwrap());
+        assert_eq!(1, Arc::strong_count(&bytes));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_numa_policy() {
+        let mut bytes = Bytes::heap_allocate_aligned(4 * 4096, 4096);
+        bytes.set_numa_policy(&NumaPolicy::Bind(vec![0])).unwrap();
+        bytes
+            .set_numa_policy(&NumaPolicy::Interleave(vec![0]))
+            .unwrap();
+        bytes.set_numa_policy(&NumaPolicy::Preferred(0)).unwrap();
+        assert!(matches!(
+            Bytes::heap_allocate(4096 + 64).set_numa_policy(&NumaPolicy::Preferred(0)),
+            Err(BytesError::Unsupported(_))
+        ));
+        bytes.lock().unwrap();
+        let nodes = bytes.page_nodes().unwrap();
+        assert_eq!(4, nodes.len());
+        //every page is resident once locked
+        assert!(nodes.iter().all(|node| node.is_some()));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_create_owned() {
+        let path = shm_path("owned");
+        let _ = fs::remove_file(&path);
+        let bytes = Bytes::create_owned(&path, 64);
+        assert!(bytes.is_owner());
+        assert!(matches!(
+            Bytes::try_create_owned(&path, 64),
+            Err(BytesError::AlreadyExists)
+        ));
+        let opened = Bytes::open_file_backed(&path);
+        assert!(!opened.is_owner());
+        drop(bytes);
+        assert!(!fs::exists(&path).unwrap());
+        assert!(!crate::segments::owner_lock_path(path.as_ref()).exists());
+    }
+
+    #[test]
+    fn test_read_modify_write() {
+        let bytes = Bytes::heap_allocate(64);
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        assert_eq!(0u64, view.fetch_add_at(0, 5, Relaxed));
+        assert_eq!(5u64, view.fetch_sub_at(0, 2, Relaxed));
+        assert_eq!(3u64, view.swap_at(0, 10, Relaxed));
+        assert_eq!(
+            Ok(10u64),
+            view.compare_exchange_at(0, 10, 11, Relaxed, Relaxed)
+        );
+        assert_eq!(
+            Err(11u64),
+            view.compare_exchange_at(0, 10, 12, Relaxed, Relaxed)
+        );
+        let mut current: u64 = view.load_at(0, Relaxed);
+        while let Err(actual) =
+            view.compare_exchange_weak_at(0, current, current * 2, Relaxed, Relaxed)
+        {
+            current = actual;
+        }
+        assert_eq!(22u64, view.load_at(0, Relaxed));
+
+        assert_eq!(0u32, view.fetch_or_at(8, 0b1100, Relaxed));
+        assert_eq!(0b1100u32, view.fetch_and_at(8, 0b0110, Relaxed));
+        assert_eq!(0b0100u32, view.fetch_xor_at(8, 0b0101, Relaxed));
+        assert_eq!(0b0001u32, view.load_at(8, Relaxed));
+
+        assert_eq!(0i16, view.fetch_sub_at(16, 3, Relaxed));
+        assert_eq!(-3i16, view.fetch_max_at(16, -7, Relaxed));
+        assert_eq!(-3i16, view.fetch_min_at(16, -7, Relaxed));
+        assert_eq!(-7i16, view.load_at(16, Relaxed));
+        assert_eq!(0u8, view.fetch_max_at(24, 200, Relaxed));
+        assert_eq!(200u8, view.fetch_add_at(24, 100, Relaxed));
+        assert_eq!(44u8, view.load_at(24, Relaxed));
+        assert_eq!(0i8, view.swap_at(32, -1, Relaxed));
+        assert_eq!(0i32, view.fetch_add_at(40, i32::MAX, Relaxed));
+        assert_eq!(0i64, view.fetch_min_at(48, i64::MIN, Relaxed));
+        assert_eq!(i64::MIN, view.load_at(48, Relaxed));
+        assert_eq!(
+            Ok(0u16),
+            view.compare_exchange_at(56, 0, 1, Relaxed, Relaxed)
+        );
+    }
+
+    #[test]
+    fn test_concurrent_fetch_add() {
+        let bytes = Bytes::heap_allocate(64);
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..10_000 {
+                        view.fetch_add_at(8, 1i64, Ordering::AcqRel);
+                    }
+                });
+            }
+        });
+        assert_eq!(40_000i64, view.load_at(8, Ordering::Acquire));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_read_only_bytes() {
+        let path = shm_path("read_only");
+        let _ = fs::remove_file(&path);
+        let bytes = Bytes::from_file_backed(&path, 64);
+        let read_only = Bytes::open_file_backed_read_only(&path);
+        assert!(read_only.is_read_only());
+        assert!(!bytes.is_read_only());
+        read_only.warm().unwrap();
+
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.store_at(16, 0xABCDu32, Ordering::Release);
+        let read_view = BytesReadView::from_bytes(0, 64, &read_only);
+        let val: u32 = read_view.load_at(16, Ordering::Acquire);
+        assert_eq!(0xABCD, val);
+        let sub_view = read_view.sub_view(16, 8);
+        assert_eq!(0xCD, sub_view.get_u8(0));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[should_panic(expected = "read only bytes")]
+    fn test_no_atomic_view_over_read_only_bytes() {
+        let path = shm_path("read_only_view");
+        let _ = fs::remove_file(&path);
+        let _bytes = Bytes::from_file_backed(&path, 64);
+        let read_only = Bytes::open_file_backed_read_only(&path);
+        fs::remove_file(&path).unwrap();
+        let _ = BytesAtomicView::from_bytes(0, 64, &read_only);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_flush() {
+        let path =
+            std::env::temp_dir().join(format!("crossbytes.test.flush.{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let bytes = Bytes::from_file_backed(&path, 8192);
+        let mut view = BytesAtomicView::from_bytes(0, 8192, &bytes);
+        view.store_at(4096, 0x0102030405060708u64, Ordering::Release);
+        bytes.flush().unwrap();
+        bytes.flush_range(4096, 8).unwrap();
+        bytes.flush_async().unwrap();
+        let sub_view = view.sub_view(4096, 16);
+        sub_view.flush().unwrap();
+        sub_view.flush_range(8, 8).unwrap();
+        sub_view.flush_async().unwrap();
+        assert!(matches!(
+            sub_view.flush_range(8, 16),
+            Err(BytesError::OutOfBounds { offset: 8, len: 16 })
+        ));
+        assert!(matches!(
+            bytes.flush_range(8192, 1),
+            Err(BytesError::OutOfBounds { .. })
+        ));
+        let contents = fs::read(&path).unwrap();
+        assert_eq!(0x08, contents[4096]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_flush_heap_unsupported() {
+        let bytes = Bytes::heap_allocate(64);
+        assert!(matches!(bytes.flush(), Err(BytesError::Unsupported(_))));
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        assert!(matches!(view.flush(), Err(BytesError::Unsupported(_))));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_flush_anon_unsupported() {
+        let bytes = Bytes::try_heap_allocate_huge(4096).unwrap();
+        assert!(matches!(bytes.flush(), Err(BytesError::Unsupported(_))));
+        let bytes = Bytes::memap_anon(memmap2::MmapOptions::new().len(4096).map_anon().unwrap());
+        assert!(matches!(bytes.flush_async(), Err(BytesError::Unsupported(_))));
+        let path = shm_path("flush_anon");
+        let file = fs::File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        file.set_len(4096).unwrap();
+        let bytes = Bytes::memap(unsafe { memmap2::MmapOptions::new().map_mut(&file) }.unwrap());
+        fs::remove_file(&path).unwrap();
+        bytes.flush_range(0, 8).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_grow_file_backed() {
+        let path = shm_path("grow");
+        let _ = fs::remove_file(&path);
+        let mut bytes = Bytes::from_file_backed(&path, 4096);
+        let mut reader = Bytes::open_file_backed_read_only(&path);
+        BytesAtomicView::from_bytes(0, 4096, &bytes).store_at(4088, 9u64, Ordering::Release);
+
+        bytes.grow(3 * 4096).unwrap();
+        assert_eq!(3 * 4096, bytes.capacity());
+        assert_eq!(1, bytes.generation());
+        assert_eq!(3 * 4096, fs::metadata(&path).unwrap().len());
+        let mut view = BytesAtomicView::from_bytes(0, bytes.capacity(), &bytes);
+        assert_eq!(9u64, view.load_at(4088, Ordering::Acquire));
+        assert_eq!(0u64, view.load_at(2 * 4096, Ordering::Acquire));
+        view.store_at(2 * 4096, 10u64, Ordering::Release);
+
+        assert_eq!(4096, reader.capacity());
+        assert!(reader.refresh().unwrap());
+        assert!(!reader.refresh().unwrap());
+        assert_eq!(3 * 4096, reader.capacity());
+        let reader_view = BytesReadView::from_bytes(0, reader.capacity(), &reader);
+        assert_eq!(10u64, reader_view.load_at(2 * 4096, Ordering::Acquire));
+
+        assert!(matches!(
+            bytes.grow(4096),
+            Err(BytesError::InvalidSize(4096))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_grow_memfd() {
+        let mut bytes = Bytes::memfd_create("crossbytes.grow", 4096, false);
+        bytes.grow(8192).unwrap();
+        assert_eq!(8192, bytes.capacity());
+        let mut sealed = Bytes::memfd_create("crossbytes.grow.sealed", 4096, true);
+        assert!(matches!(
+            sealed.grow(8192),
+            Err(BytesError::PermissionDenied)
+        ));
+        assert_eq!(4096, sealed.capacity());
+        let mut heap = Bytes::heap_allocate(64);
+        assert!(matches!(heap.grow(128), Err(BytesError::Unsupported(_))));
+    }
 
     #[test]
     fn test_atomic_ref() {
@@ -244,9 +2947,9 @@ mod tests {
         let atomic_ref: &AtomicU64 = buffer.get_atomic(0);
         atomic_ref.store(0xFF00FFu64, Ordering::Relaxed);
         assert_eq!(atomic_ref.load(Ordering::Relaxed), 0xFF00FFu64);
-        assert_eq!(*(&buffer[0]), 0xFFu8);
-        assert_eq!(*(&buffer[1]), 0u8);
-        assert_eq!(*(&buffer[2]), 0xFFu8);
+        assert_eq!(buffer.get_u8(0), 0xFFu8);
+        assert_eq!(buffer.get_u8(1), 0u8);
+        assert_eq!(buffer.get_u8(2), 0xFFu8);
     }
 
     #[test]
@@ -262,14 +2965,14 @@ mod tests {
         let atomic16: &AtomicU16 = buffer.get_atomic(12);
         atomic16.store(0xF0FFu16, Ordering::Relaxed);
 
-        assert_eq!(*(&buffer[0]), 0xFFu8);
-        assert_eq!(*(&buffer[2]), 0xF0u8);
+        assert_eq!(buffer.get_u8(0), 0xFFu8);
+        assert_eq!(buffer.get_u8(2), 0xF0u8);
 
-        assert_eq!(*(&buffer[8]), 0xFFu8);
-        assert_eq!(*(&buffer[10]), 0xF0u8);
+        assert_eq!(buffer.get_u8(8), 0xFFu8);
+        assert_eq!(buffer.get_u8(10), 0xF0u8);
 
-        assert_eq!(*(&buffer[12]), 0xFFu8);
-        assert_eq!(*(&buffer[13]), 0xF0u8);
+        assert_eq!(buffer.get_u8(12), 0xFFu8);
+        assert_eq!(buffer.get_u8(13), 0xF0u8);
     }
 
     #[test]
@@ -285,7 +2988,7 @@ mod tests {
         let bytes = Bytes::heap_allocate(32);
         let mut buffer: BytesAtomicView = BytesAtomicView::from_bytes(0, 16, &bytes);
         buffer.store_at(8, 8u64, Relaxed);
-        let mut sub_slice = buffer.sub_slice(8..);
+        let sub_slice = buffer.sub_slice(8..);
         let val: u64 = buffer.load_at(8, Relaxed);
         assert_eq!(val, sub_slice.load_at(0, Relaxed))
     }
@@ -297,6 +3000,381 @@ mod tests {
         let _: &AtomicU64 = buffer.get_atomic(2);
     }
 
+    #[test]
+    #[should_panic(expected = "bounds error offset=12, size=8, length=16")]
+    fn test_disallow_access_past_end() {
+        let bytes = Bytes::heap_allocate(32);
+        let buffer = BytesAtomicView::from_bytes(0, 16, &bytes);
+        //starts inside the view but the last 4 bytes are outside of it
+        let _: u64 = buffer.load_at(12, Relaxed);
+    }
+
+    #[test]
+    fn test_float_load_store() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.store_at(0, 101.25f64, Relaxed);
+        assert_eq!(101.25f64, view.load_at(0, Relaxed));
+        assert_eq!(
+            101.25f64.to_bits(),
+            LoadStore::<u64>::load_at(&view, 0, Relaxed)
+        );
+        assert_eq!(101.25f64, view.fetch_add_at(0, 0.5, Relaxed));
+        assert_eq!(101.75f64, view.fetch_sub_at(0, 1.75, Relaxed));
+        assert_eq!(100.0f64, view.fetch_max_at(0, 99.0, Relaxed));
+        assert_eq!(100.0f64, view.fetch_min_at(0, 99.0, Relaxed));
+        assert_eq!(
+            Ok(99.0f64),
+            view.compare_exchange_at(0, 99.0, -0.0, Relaxed, Relaxed)
+        );
+        //bit patterns are compared
+        assert!(view
+            .compare_exchange_at(0, 0.0f64, 1.0, Relaxed, Relaxed)
+            .is_err());
+        //clears the sign bit
+        assert_eq!(
+            -0.0f64,
+            view.fetch_and_at(0, f64::from_bits(!(1 << 63)), Relaxed)
+        );
+        assert!(LoadStore::<f64>::load_at(&view, 0, Relaxed).is_sign_positive());
+
+        view.store_at(8, 0.5f32, Relaxed);
+        assert_eq!(0.5f32, view.swap_at(8, 2.0, Relaxed));
+        assert_eq!(2.0f32, view.fetch_add_at(8, 1.0, Relaxed));
+        let read_view = BytesReadView::from(view.clone());
+        assert_eq!(3.0f32, read_view.load_at(8, Relaxed));
+        assert_eq!(Ok(0.0f64), read_view.try_load_at(0, Relaxed));
+    }
+
+    #[test]
+    fn test_concurrent_float_fetch_add() {
+        let bytes = Bytes::heap_allocate(64);
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        let count = if cfg!(miri) { 100 } else { 10_000 };
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..count {
+                        view.fetch_add_at(8, 0.5f64, Ordering::AcqRel);
+                    }
+                });
+            }
+        });
+        assert_eq!(2.0 * count as f64, view.load_at(8, Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_pointer_sized_and_bool() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.store_at(0, usize::MAX, Relaxed);
+        assert_eq!(usize::MAX, view.fetch_add_at(0, 1, Relaxed));
+        assert_eq!(0usize, view.load_at(0, Relaxed));
+        view.store_at(8, -5isize, Relaxed);
+        assert_eq!(-5isize, view.fetch_max_at(8, 3, Relaxed));
+        let index: &AtomicUsize = view.get_atomic(16);
+        index.store(42, Relaxed);
+
+        view.store_at(24, true, Ordering::Release);
+        assert!(LoadStore::<bool>::load_at(&view, 24, Ordering::Acquire));
+        let flag: &AtomicBool = view.get_atomic(24);
+        assert!(flag.swap(false, Relaxed));
+
+        let read_view = BytesReadView::from(view.clone());
+        assert_eq!(42usize, read_view.load_at(16, Relaxed));
+        assert_eq!(3isize, read_view.load_at(8, Relaxed));
+        assert!(!AtomicLoad::<bool>::load_at(&read_view, 24, Relaxed));
+    }
+
+    #[test]
+    fn test_plain_accessors() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.put_u16_be(1, 0x0102);
+        assert_eq!(vec![0x01, 0x02], view.sub_view(1, 2).to_vec());
+        assert_eq!(0x0201, view.get_u16_le(1));
+        view.put_i64_le(3, -2);
+        assert_eq!(-2, view.get_i64_le(3));
+        assert_eq!((-2i64).swap_bytes(), view.get_i64_be(3));
+        view.put_u32_ne(11, 7);
+        assert_eq!(7, view.get_u32_ne(11));
+        view.put_f64_be(17, 1.5);
+        assert_eq!(1.5, view.get_f64_be(17));
+        assert_eq!(1.5f64.to_be_bytes().to_vec(), view.sub_view(17, 8).to_vec());
+        view.put_f32_le(25, -0.25);
+        assert_eq!(-0.25, view.get_f32_le(25));
+        view.put_i16_ne(29, -300);
+        view.put_u64_be(31, u64::MAX - 1);
+        view.put_i32_be(39, i32::MIN);
+        view.put_u8(43, 200);
+        view.put_i8(44, -100);
+
+        let read_view = BytesReadView::from(view.sub_view(0, 64));
+        assert_eq!(-300, read_view.get_i16_ne(29));
+        assert_eq!(u64::MAX - 1, read_view.get_u64_be(31));
+        assert_eq!(i32::MIN, read_view.get_i32_be(39));
+        assert_eq!(200, read_view.get_u8(43));
+        assert_eq!(-100, read_view.get_i8(44));
+        assert_eq!(0x0102, read_view.get_u16_be(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds error offset=60, size=8, length=64")]
+    fn test_plain_access_past_end() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.put_f64_le(60, 1.0);
+    }
+
+    #[test]
+    fn test_ordered_accessors() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.put_i64_release(8, -5);
+        assert_eq!(-5, view.get_i64_acquire(8));
+        assert_eq!(-5i64, view.load_at(8, Ordering::Acquire));
+        view.put_u32_volatile(16, 7);
+        assert_eq!(7, view.get_u32_volatile(16));
+        view.put_f64_opaque(24, 2.5);
+        assert_eq!(2.5, view.get_f64_opaque(24));
+        view.put_u8_release(33, 9);
+        assert_eq!(9, view.get_u8_plain(33));
+        //plain access does not need alignment
+        view.put_u64_plain(35, u64::MAX - 3);
+        assert_eq!(u64::MAX - 3, view.get_u64_plain(35));
+        assert_eq!(u64::MAX - 3, view.get_u64_ne(35));
+        view.put_usize_volatile(48, 11);
+        assert_eq!(11, view.get_usize_opaque(48));
+        assert_eq!(
+            Err(AccessError::Misaligned {
+                offset: 35,
+                align: 8
+            }),
+            LoadStore::<u64>::try_load_at(&view, 35, Ordering::Relaxed)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid alignment offset=3, Atomic type align=4")]
+    fn test_ordered_access_misaligned() {
+        let bytes = Bytes::heap_allocate(64);
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.get_i32_volatile(3);
+    }
+
+    #[test]
+    fn test_strings_and_bytes() {
+        let bytes = Bytes::heap_allocate(128);
+        let mut view = BytesAtomicView::from_bytes(0, 128, &bytes);
+        assert_eq!(7, view.put_string_ascii(0, "EUR"));
+        assert_eq!(3, view.get_i32_le(0));
+        assert_eq!(b"EUR".to_vec(), view.sub_view(4, 3).to_vec());
+        assert_eq!("EUR", view.get_string_ascii(0));
+        assert_eq!(8, view.put_string_ascii(8, "né?é"));
+        assert_eq!("n???", view.get_string_ascii(8));
+        assert_eq!(10, view.put_string_utf8(16, "€/é"));
+        assert_eq!("€/é", view.get_string_utf8(16));
+        assert_eq!(7, view.put_bytes(32, &[1, 2, 3]));
+        assert_eq!(vec![1, 2, 3], view.get_bytes(32));
+        view.put_u8(20, 0xFF);
+        assert_eq!("\u{FFFD}\u{FFFD}\u{FFFD}/é", view.get_string_utf8(16));
+        view.put_i32_le(40, 0);
+        assert_eq!("", view.get_string_utf8(40));
+
+        view.set_memory(64, 16, 0xAB);
+        assert_eq!(0xABAB_ABAB_ABAB_ABABu64, view.get_u64_le(72));
+        assert_eq!(0, view.get_u8(80));
+        let read_view = BytesReadView::from(view.clone());
+        assert_eq!("€/é".len(), read_view.get_bytes(16).len());
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds error offset=60, size=9, length=64")]
+    fn test_put_string_past_end() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.put_string_utf8(60, "hello");
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds error offset=4, size=4294967295, length=64")]
+    fn test_get_bytes_negative_length() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.put_i32_le(0, -1);
+        view.get_bytes(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds error offset=12, size=53, length=64")]
+    fn test_get_string_length_out_of_bounds() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.put_i32_le(8, 53);
+        view.get_string_utf8(8);
+    }
+
+    #[test]
+    fn test_copy_between_views() {
+        let bytes = Bytes::heap_allocate(64);
+        let other = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        let mut dst = BytesAtomicView::from_bytes(0, 64, &other);
+        view.copy_from_slice(0, b"0123456789");
+        copy_between_views(view.clone(), 2, &mut dst, 8, 4);
+        assert_eq!(b"2345".to_vec(), dst.sub_view(8, 4).to_vec());
+        //overlapping forwards and backwards
+        let src = view.clone();
+        copy_between_views(src.clone(), 0, &mut view, 2, 6);
+        assert_eq!(b"0101234589".to_vec(), view.sub_view(0, 10).to_vec());
+        copy_between_views(src, 2, &mut view, 0, 6);
+        assert_eq!(b"0123454589".to_vec(), view.sub_view(0, 10).to_vec());
+
+        let (mut head, mut tail) = view.split_at(32);
+        copy_between_views(&head, 0, &mut tail, 0, 4);
+        copy_between_views(tail, 2, &mut head, 16, 2);
+        copy_between_views(&head, 16, &mut dst, 0, 2);
+        assert_eq!(b"23".to_vec(), head.sub_view(16, 2).to_vec());
+        assert_eq!(b"23".to_vec(), dst.sub_view(0, 2).to_vec());
+    }
+
+    #[test]
+    fn test_split_views() {
+        let bytes = Bytes::heap_allocate(64);
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        let (mut head, tail) = view.split_at(40);
+        assert_eq!((40, 24), (head.len(), tail.len()));
+        head.store_at(32, 6u64, Relaxed);
+        assert_eq!(6u64, head.fetch_add_at(32, 1, Relaxed));
+        assert_eq!(7, head.sub_view(32, 8).get_u64_le(0));
+        let mut chunks = tail.chunks_exact(8);
+        assert_eq!(3, chunks.len());
+        for (i, mut chunk) in chunks.by_ref().enumerate() {
+            assert_eq!(8, chunk.len());
+            chunk.put_u64_le(0, i as u64 + 1);
+        }
+        assert!(chunks.into_remainder().is_empty());
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        assert_eq!(7u64, view.load_at(32, Relaxed));
+        assert_eq!(3, view.get_u64_le(56));
+
+        let base = view.as_ptr() as usize;
+        let mut chunks = view.chunks_exact(24);
+        let (first, second) = chunks.next().unwrap().split_at(0);
+        assert_eq!((0, 24), (first.len(), second.len()));
+        assert_eq!(24, chunks.next().unwrap().as_ptr() as usize - base);
+        assert!(chunks.next().is_none());
+        let remainder = chunks.into_remainder().into_view();
+        assert_eq!((16, 2), (remainder.len(), remainder.get_u64_le(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "split offset=65 past the end, length=64")]
+    fn test_split_past_end() {
+        let bytes = Bytes::heap_allocate(64);
+        let _ = BytesAtomicView::from_bytes(0, 64, &bytes).split_at(65);
+    }
+
+    #[test]
+    fn test_copy_bytes() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.copy_from_slice(3, b"hello world");
+        let mut dst = [0u8; 5];
+        view.copy_to_slice(9, &mut dst);
+        assert_eq!(b"world", &dst);
+        assert_eq!(b'h', view.as_atomic_bytes()[3].load(Ordering::Relaxed));
+        view.as_atomic_bytes()[4].store(b'a', Ordering::Relaxed);
+        let read_view = BytesReadView::from(view.sub_view(3, 5));
+        assert_eq!(5, read_view.len());
+        assert_eq!(b"hallo".to_vec(), read_view.to_vec());
+        let mut empty = [0u8; 0];
+        read_view.copy_to_slice(5, &mut empty);
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds error offset=60, size=5, length=64")]
+    fn test_copy_past_end() {
+        let bytes = Bytes::heap_allocate(64);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.copy_from_slice(60, b"hello");
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds error offset=4294967288, size=16, length=64")]
+    fn test_sub_view_overflow() {
+        let bytes = Bytes::heap_allocate(64);
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.sub_view(0xFFFF_FFF8, 0x10);
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds error offset=8, size=")]
+    fn test_view_from_bytes_overflow() {
+        let bytes = Bytes::heap_allocate(64);
+        BytesAtomicView::from_bytes(8, usize::MAX, &bytes);
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds error offset=4294967288, size=16, length=64")]
+    fn test_read_sub_view_overflow() {
+        let bytes = Bytes::heap_allocate(64);
+        let view = BytesReadView::from_bytes(0, 64, &bytes);
+        view.sub_view(0xFFFF_FFF8, 0x10);
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds error offset=8, size=")]
+    fn test_read_view_from_bytes_overflow() {
+        let bytes = Bytes::heap_allocate(64);
+        BytesReadView::from_bytes(8, usize::MAX, &bytes);
+    }
+
+    #[test]
+    fn test_checked_access() {
+        let bytes = Bytes::heap_allocate(32);
+        let mut buffer = BytesAtomicView::from_bytes(0, 16, &bytes);
+        assert_eq!(Ok(()), buffer.try_store_at(8, 5u64, Relaxed));
+        assert_eq!(Ok(5u64), buffer.try_load_at(8, Relaxed));
+        assert_eq!(
+            Err(AccessError::OutOfBounds {
+                offset: 12,
+                size: 8,
+                length: 16
+            }),
+            buffer.try_store_at(12, 1u64, Relaxed)
+        );
+        assert_eq!(
+            Err(AccessError::OutOfBounds {
+                offset: usize::MAX,
+                size: 1,
+                length: 16
+            }),
+            LoadStore::<u8>::try_load_at(&buffer, usize::MAX, Relaxed)
+        );
+        assert_eq!(
+            Err(AccessError::Misaligned {
+                offset: 6,
+                align: 4
+            }),
+            LoadStore::<u32>::try_load_at(&buffer, 6, Relaxed)
+        );
+        let atomic: Result<&AtomicU16, _> = buffer.try_get_atomic(14);
+        assert!(atomic.is_ok());
+
+        unsafe {
+            buffer.store_at_unchecked(0, 3u32, Relaxed);
+            assert_eq!(3u32, buffer.load_at_unchecked(0, Relaxed));
+            let atomic: &AtomicU64 = buffer.get_atomic_unchecked(8);
+            assert_eq!(5, atomic.load(Relaxed));
+        }
+
+        let read_view = BytesReadView::from(buffer);
+        assert_eq!(Ok(5u64), read_view.try_load_at(8, Relaxed));
+        assert!(AtomicLoad::<u32>::try_load_at(&read_view, 14, Relaxed).is_err());
+        assert_eq!(3u32, unsafe { read_view.load_at_unchecked(0, Relaxed) });
+    }
+
     #[test]
     #[should_panic(expected = "invalid alignment offset=3, Atomic type")]
     fn test_disallow_unaligned_u16_access() {
@@ -0,0 +1,327 @@
+use crate::bytes::{AtomicLoad, Bytes, BytesAtomicView, BytesError, BytesReadView, LoadStore};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+// layout of the header written at offset 0 of a segment
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 8;
+const KIND_OFFSET: usize = 12;
+const CAPACITY_OFFSET: usize = 16;
+const PID_OFFSET: usize = 24;
+const START_TIME_OFFSET: usize = 32;
+const READY_OFFSET: usize = 40;
+
+/// length of the header, the region starts directly after it
+pub const HEADER_LENGTH: usize = 64;
+pub const MAGIC: u64 = u64::from_le_bytes(*b"XBYTESHD");
+pub const VERSION: u32 = 1;
+
+/// what the region after the header holds
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RegionKind {
+    Raw,
+    Broadcast,
+    // application defined, use values >= 1024 to not clash with future kinds
+    Custom(u32),
+}
+
+impl RegionKind {
+    fn to_u32(self) -> u32 {
+        match self {
+            RegionKind::Raw => 1,
+            RegionKind::Broadcast => 2,
+            RegionKind::Custom(kind) => kind,
+        }
+    }
+
+    fn from_u32(kind: u32) -> RegionKind {
+        match kind {
+            1 => RegionKind::Raw,
+            2 => RegionKind::Broadcast,
+            kind => RegionKind::Custom(kind),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HeaderError {
+    // segment is smaller than the header
+    TooSmall(usize),
+    BadMagic(u64),
+    UnsupportedVersion(u32),
+    KindMismatch {
+        expected: RegionKind,
+        actual: RegionKind,
+    },
+    // capacity in the header is larger than the segment
+    CapacityMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    // creator has not finished setting up the segment
+    NotReady,
+}
+
+impl Display for HeaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderError::TooSmall(size) => write!(f, "segment of {} bytes too small", size),
+            HeaderError::BadMagic(magic) => write!(f, "bad magic {:#x}", magic),
+            HeaderError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {}", version)
+            }
+            HeaderError::KindMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "kind mismatch expected={:?}, actual={:?}",
+                    expected, actual
+                )
+            }
+            HeaderError::CapacityMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "capacity mismatch expected={}, actual={}",
+                    expected, actual
+                )
+            }
+            HeaderError::NotReady => write!(f, "segment not ready"),
+        }
+    }
+}
+
+impl From<HeaderError> for BytesError {
+    fn from(err: HeaderError) -> Self {
+        BytesError::InvalidHeader(err)
+    }
+}
+
+/// self describing header at offset 0 of a segment, written by the creator
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SegmentHeader {
+    pub version: u32,
+    pub kind: RegionKind,
+    // length of the region following the header
+    pub capacity: u64,
+    pub pid: u32,
+    // start time of the creator in clock ticks since boot, tells a reused pid apart
+    pub start_time: u64,
+}
+
+impl SegmentHeader {
+    /// write the header for a region of kind filling the rest of bytes.
+    /// receivers refuse to attach until `mark_ready` is called
+    pub fn init(bytes: &Bytes, kind: RegionKind) -> Result<SegmentHeader, BytesError> {
+        if bytes.is_read_only() {
+            return Err(BytesError::Unsupported("header init"));
+        }
+        let size = bytes.capacity();
+        if size < HEADER_LENGTH {
+            return Err(HeaderError::TooSmall(size).into());
+        }
+        let pid = std::process::id();
+        let header = SegmentHeader {
+            version: VERSION,
+            kind,
+            capacity: (size - HEADER_LENGTH) as u64,
+            pid,
+            //miri can not read /proc with isolation enabled, 0 is an unknown start time
+            start_time: if cfg!(miri) {
+                0
+            } else {
+                process_start_time(pid).unwrap_or(0)
+            },
+        };
+        let mut view = BytesAtomicView::from_bytes(0, HEADER_LENGTH, bytes);
+        view.store_at(READY_OFFSET, 0u32, Release);
+        view.store_at(MAGIC_OFFSET, MAGIC, Relaxed);
+        view.store_at(VERSION_OFFSET, header.version, Relaxed);
+        view.store_at(KIND_OFFSET, header.kind.to_u32(), Relaxed);
+        view.store_at(CAPACITY_OFFSET, header.capacity, Relaxed);
+        view.store_at(PID_OFFSET, header.pid, Relaxed);
+        view.store_at(START_TIME_OFFSET, header.start_time, Relaxed);
+        Ok(header)
+    }
+
+    /// publish the segment, all writes made before this are visible to receivers
+    /// which validated the header
+    pub fn mark_ready(bytes: &Bytes) {
+        let mut view = BytesAtomicView::from_bytes(0, HEADER_LENGTH, bytes);
+        view.store_at(READY_OFFSET, 1u32, Release);
+    }
+
+    /// read and check the header is a ready segment of the current version.
+    /// the segment may have grown since it was created but must still hold the region
+    pub fn read(bytes: &Bytes) -> Result<SegmentHeader, BytesError> {
+        let size = bytes.capacity();
+        if size < HEADER_LENGTH {
+            return Err(HeaderError::TooSmall(size).into());
+        }
+        let view = BytesReadView::from_bytes(0, HEADER_LENGTH, bytes);
+        let ready: u32 = view.load_at(READY_OFFSET, Acquire);
+        let magic: u64 = view.load_at(MAGIC_OFFSET, Relaxed);
+        if magic != MAGIC {
+            return Err(HeaderError::BadMagic(magic).into());
+        }
+        if ready == 0 {
+            return Err(HeaderError::NotReady.into());
+        }
+        let version: u32 = view.load_at(VERSION_OFFSET, Relaxed);
+        if version != VERSION {
+            return Err(HeaderError::UnsupportedVersion(version).into());
+        }
+        let header = SegmentHeader {
+            version,
+            kind: RegionKind::from_u32(view.load_at(KIND_OFFSET, Relaxed)),
+            capacity: view.load_at(CAPACITY_OFFSET, Relaxed),
+            pid: view.load_at(PID_OFFSET, Relaxed),
+            start_time: view.load_at(START_TIME_OFFSET, Relaxed),
+        };
+        let actual = (size - HEADER_LENGTH) as u64;
+        if header.capacity > actual {
+            return Err(HeaderError::CapacityMismatch {
+                expected: header.capacity,
+                actual,
+            }
+            .into());
+        }
+        Ok(header)
+    }
+
+    /// read the header and check it describes a region of kind
+    pub fn validate(bytes: &Bytes, kind: RegionKind) -> Result<SegmentHeader, BytesError> {
+        let header = Self::read(bytes)?;
+        if header.kind != kind {
+            return Err(HeaderError::KindMismatch {
+                expected: kind,
+                actual: header.kind,
+            }
+            .into());
+        }
+        Ok(header)
+    }
+}
+
+/// start time of the process in clock ticks since boot, from /proc/pid/stat
+pub(crate) fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    //the command name can contain spaces, fields are counted from after it
+    let (_, fields) = stat.rsplit_once(')')?;
+    //starttime is field 22, the first field after the name is field 3
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::{Bytes, BytesAtomicView, BytesError, LoadStore};
+    use crate::header::{
+        process_start_time, HeaderError, RegionKind, SegmentHeader, HEADER_LENGTH, VERSION,
+    };
+    use std::fs;
+    use std::sync::atomic::Ordering::Relaxed;
+
+    #[test]
+    fn test_header_round_trip() {
+        let bytes = Bytes::heap_allocate(HEADER_LENGTH + 256);
+        let written = SegmentHeader::init(&bytes, RegionKind::Custom(1024)).unwrap();
+        assert!(matches!(
+            SegmentHeader::read(&bytes),
+            Err(BytesError::InvalidHeader(HeaderError::NotReady))
+        ));
+        SegmentHeader::mark_ready(&bytes);
+        let read = SegmentHeader::validate(&bytes, RegionKind::Custom(1024)).unwrap();
+        assert_eq!(written, read);
+        assert_eq!(VERSION, read.version);
+        assert_eq!(256, read.capacity);
+        assert_eq!(std::process::id(), read.pid);
+        if !cfg!(miri) {
+            assert_eq!(
+                process_start_time(std::process::id()),
+                Some(read.start_time)
+            );
+        }
+    }
+
+    #[test]
+    fn test_header_mismatch() {
+        let bytes = Bytes::heap_allocate(HEADER_LENGTH + 64);
+        assert!(matches!(
+            SegmentHeader::read(&bytes),
+            Err(BytesError::InvalidHeader(HeaderError::BadMagic(0)))
+        ));
+        SegmentHeader::init(&bytes, RegionKind::Raw).unwrap();
+        SegmentHeader::mark_ready(&bytes);
+        assert!(matches!(
+            SegmentHeader::validate(&bytes, RegionKind::Broadcast),
+            Err(BytesError::InvalidHeader(HeaderError::KindMismatch {
+                expected: RegionKind::Broadcast,
+                actual: RegionKind::Raw
+            }))
+        ));
+        let mut view = BytesAtomicView::from_bytes(0, HEADER_LENGTH, &bytes);
+        view.store_at(8, VERSION + 1, Relaxed);
+        assert!(matches!(
+            SegmentHeader::read(&bytes),
+            Err(BytesError::InvalidHeader(HeaderError::UnsupportedVersion(
+                _
+            )))
+        ));
+        view.store_at(8, VERSION, Relaxed);
+        view.store_at(16, 65u64, Relaxed);
+        assert!(matches!(
+            SegmentHeader::read(&bytes),
+            Err(BytesError::InvalidHeader(HeaderError::CapacityMismatch {
+                expected: 65,
+                actual: 64
+            }))
+        ));
+        let small = Bytes::heap_allocate(32);
+        assert!(matches!(
+            SegmentHeader::read(&small),
+            Err(BytesError::InvalidHeader(HeaderError::TooSmall(32)))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_open_validated() {
+        let path = format!("/dev/shm/crossbytes.test.header.{}", std::process::id());
+        let _ = fs::remove_file(&path);
+        let bytes = Bytes::from_file_backed(&path, (HEADER_LENGTH + 128) as u64);
+        assert!(Bytes::open_validated(&path, RegionKind::Raw).is_err());
+        SegmentHeader::init(&bytes, RegionKind::Raw).unwrap();
+        SegmentHeader::mark_ready(&bytes);
+        let opened = Bytes::open_validated(&path, RegionKind::Raw).unwrap();
+        assert_eq!(HEADER_LENGTH + 128, opened.capacity());
+        assert!(matches!(
+            Bytes::open_validated(&path, RegionKind::Broadcast),
+            Err(BytesError::InvalidHeader(HeaderError::KindMismatch { .. }))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_header_grown_and_read_only() {
+        let path = format!(
+            "/dev/shm/crossbytes.test.header.grow.{}",
+            std::process::id()
+        );
+        let _ = fs::remove_file(&path);
+        let mut bytes = Bytes::from_file_backed(&path, (HEADER_LENGTH + 128) as u64);
+        SegmentHeader::init(&bytes, RegionKind::Raw).unwrap();
+        SegmentHeader::mark_ready(&bytes);
+        bytes.grow(8192).unwrap();
+        let header = SegmentHeader::validate(&bytes, RegionKind::Raw).unwrap();
+        assert_eq!(128, header.capacity);
+        let opened = Bytes::open_validated(&path, RegionKind::Raw).unwrap();
+        assert_eq!(8192, opened.capacity());
+
+        let read_only = Bytes::open_file_backed_read_only(&path);
+        assert!(matches!(
+            SegmentHeader::init(&read_only, RegionKind::Raw),
+            Err(BytesError::Unsupported(_))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,2 +1,16 @@
+// lets the overlays derived in tests name ::crossbytes
+#[cfg(test)]
+extern crate self as crossbytes;
+
 pub mod bytes;
+pub mod options;
 pub mod broadcast;
+pub mod header;
+pub mod segments;
+pub mod overlay;
+#[cfg(target_os = "linux")]
+pub mod memfd;
+#[cfg(target_os = "linux")]
+pub mod shm;
+#[cfg(target_os = "linux")]
+mod pages;
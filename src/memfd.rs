@@ -0,0 +1,169 @@
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::UnixStream;
+
+/// create an anonymous memory backed file of size bytes, nothing is left behind in /dev/shm
+/// if seal_size is set the size can no longer be changed by anyone holding the fd
+pub(crate) fn create(name: &str, size: u64, seal_size: bool) -> io::Result<OwnedFd> {
+    let c_name = CString::new(name)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "name contains nul byte"))?;
+    let mut flags = libc::MFD_CLOEXEC;
+    if seal_size {
+        flags |= libc::MFD_ALLOW_SEALING;
+    }
+    let raw_fd = unsafe { libc::memfd_create(c_name.as_ptr(), flags) };
+    if raw_fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+    let file = File::from(fd);
+    file.set_len(size)?;
+    let fd = OwnedFd::from(file);
+    if seal_size {
+        let res = unsafe {
+            libc::fcntl(
+                fd.as_raw_fd(),
+                libc::F_ADD_SEALS,
+                libc::F_SEAL_GROW | libc::F_SEAL_SHRINK,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(fd)
+}
+
+/// true if the memfd can neither grow nor shrink,
+/// receivers can check this before trusting the size of a received fd
+pub fn is_size_sealed(fd: BorrowedFd<'_>) -> io::Result<bool> {
+    let seals = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GET_SEALS) };
+    if seals < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let size_seals = libc::F_SEAL_GROW | libc::F_SEAL_SHRINK;
+    Ok(seals & size_seals == size_seals)
+}
+
+// large enough and aligned for a single SCM_RIGHTS control message
+#[repr(C, align(8))]
+struct CmsgBuffer([u8; 32]);
+
+/// send fd to the peer of stream using SCM_RIGHTS
+pub fn send_fd(stream: &UnixStream, fd: BorrowedFd<'_>) -> io::Result<()> {
+    let mut data = [0u8; 1];
+    let mut iov = libc::iovec {
+        iov_base: data.as_mut_ptr() as *mut libc::c_void,
+        iov_len: data.len(),
+    };
+    let mut cmsg_buf = CmsgBuffer([0u8; 32]);
+    let cmsg_space = unsafe { libc::CMSG_SPACE(size_of::<RawFd>() as u32) } as usize;
+    debug_assert!(cmsg_space <= cmsg_buf.0.len());
+
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = cmsg_buf.0.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = cmsg_space as _;
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<RawFd>() as u32) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut RawFd, fd.as_raw_fd());
+    }
+    let sent = unsafe { libc::sendmsg(stream.as_raw_fd(), &msg, 0) };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// receive a fd sent by the peer of stream with `send_fd`
+pub fn recv_fd(stream: &UnixStream) -> io::Result<OwnedFd> {
+    let mut data = [0u8; 1];
+    let mut iov = libc::iovec {
+        iov_base: data.as_mut_ptr() as *mut libc::c_void,
+        iov_len: data.len(),
+    };
+    let mut cmsg_buf = CmsgBuffer([0u8; 32]);
+
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = cmsg_buf.0.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = cmsg_buf.0.len() as _;
+    let received = unsafe { libc::recvmsg(stream.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC) };
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if received == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "peer closed before sending fd",
+        ));
+    }
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        if cmsg.is_null()
+            || (*cmsg).cmsg_level != libc::SOL_SOCKET
+            || (*cmsg).cmsg_type != libc::SCM_RIGHTS
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message did not contain a fd",
+            ));
+        }
+        let raw_fd = std::ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const RawFd);
+        Ok(OwnedFd::from_raw_fd(raw_fd))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::{Bytes, BytesAtomicView, LoadStore};
+    use crate::memfd::{is_size_sealed, recv_fd, send_fd};
+    use std::fs::File;
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::Ordering::{Acquire, Release};
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_send_receive_memfd() {
+        let (tx_sock, rx_sock) = UnixStream::pair().unwrap();
+        let bytes = Bytes::memfd_create("crossbytes.test", 64, false);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.store_at(0, 99u64, Release);
+
+        send_fd(&tx_sock, bytes.fd().unwrap()).unwrap();
+        let fd = recv_fd(&rx_sock).unwrap();
+        let received = Bytes::try_from_memfd(fd, Some(64)).unwrap();
+        let received_view = BytesAtomicView::from_bytes(0, 64, &received);
+        assert_eq!(99u64, received_view.load_at(0, Acquire));
+
+        //writes are visible both ways
+        received_view.clone().store_at(8, 7u64, Release);
+        assert_eq!(7u64, view.load_at(8, Acquire));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_sealed_memfd_cannot_resize() {
+        let bytes = Bytes::memfd_create("crossbytes.sealed", 64, true);
+        let fd = bytes.fd().unwrap();
+        assert!(is_size_sealed(fd).unwrap());
+        let file = File::from(fd.try_clone_to_owned().unwrap());
+        assert!(file.set_len(128).is_err());
+        assert!(file.set_len(32).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_unsealed_memfd() {
+        let bytes = Bytes::memfd_create("crossbytes.unsealed", 64, false);
+        assert!(!is_size_sealed(bytes.fd().unwrap()).unwrap());
+    }
+}
@@ -0,0 +1,530 @@
+#[cfg(target_os = "linux")]
+use crate::bytes::PageMode;
+use crate::bytes::{Bytes, BytesError, NumaPolicy, Segment, CACHE_LINE_SIZE};
+use crate::segments::OwnerLock;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+/// memory backing a `Bytes`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Backing {
+    Heap,
+    File(PathBuf),
+    // posix shared memory segment, visible as /dev/shm/name
+    #[cfg(target_os = "linux")]
+    Shm(String),
+    // anonymous memfd, name is only used for debugging
+    #[cfg(target_os = "linux")]
+    MemFd(String),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OpenMode {
+    // create a new segment, fails if it already exists
+    Create,
+    // open an existing segment, fails if it does not exist
+    Open,
+    // open the segment if it exists otherwise create it
+    CreateOrOpen,
+}
+
+/// declarative description of a `Bytes`, eg
+/// ```no_run
+/// use crossbytes::options::{Backing, BytesOptions, OpenMode};
+/// let bytes = BytesOptions::new()
+///     .backing(Backing::File("/dev/shm/broadcast".into()))
+///     .open_mode(OpenMode::CreateOrOpen)
+///     .size(4096 + 128)
+///     .prefault(true)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BytesOptions {
+    size: Option<u64>,
+    backing: Backing,
+    alignment: usize,
+    open_mode: OpenMode,
+    permissions: u32,
+    prefault: bool,
+    preallocate: bool,
+    huge_pages: bool,
+    lock: bool,
+    numa_policy: Option<NumaPolicy>,
+    seal_size: bool,
+    unlink_on_drop: bool,
+    owner_lock: bool,
+    read_only: bool,
+}
+
+impl Default for BytesOptions {
+    fn default() -> Self {
+        BytesOptions {
+            size: None,
+            backing: Backing::Heap,
+            alignment: CACHE_LINE_SIZE,
+            open_mode: OpenMode::Create,
+            permissions: 0o666,
+            prefault: false,
+            preallocate: false,
+            huge_pages: false,
+            lock: false,
+            numa_policy: None,
+            seal_size: false,
+            unlink_on_drop: false,
+            owner_lock: false,
+            read_only: false,
+        }
+    }
+}
+
+impl BytesOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// size in bytes, required when creating. when opening the existing size must match it
+    pub fn size(&mut self, size: u64) -> &mut Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn backing(&mut self, backing: Backing) -> &mut Self {
+        self.backing = backing;
+        self
+    }
+
+    /// minimum alignment of the start of the memory, must be a power of two.
+    /// mappings are always page aligned
+    pub fn alignment(&mut self, alignment: usize) -> &mut Self {
+        self.alignment = alignment;
+        self
+    }
+
+    pub fn open_mode(&mut self, open_mode: OpenMode) -> &mut Self {
+        self.open_mode = open_mode;
+        self
+    }
+
+    /// permission bits for created files and shm segments, the process umask applies
+    pub fn permissions(&mut self, mode: u32) -> &mut Self {
+        self.permissions = mode;
+        self
+    }
+
+    /// fault in all pages writable before returning, see `Bytes::warm`
+    pub fn prefault(&mut self, prefault: bool) -> &mut Self {
+        self.prefault = prefault;
+        self
+    }
+
+    /// fallocate the backing file, see `Bytes::preallocate`
+    pub fn preallocate(&mut self, preallocate: bool) -> &mut Self {
+        self.preallocate = preallocate;
+        self
+    }
+
+    /// use explicit huge pages for heap memory and transparent huge pages otherwise,
+    /// check `Bytes::page_mode` for the mode which took effect
+    pub fn huge_pages(&mut self, huge_pages: bool) -> &mut Self {
+        self.huge_pages = huge_pages;
+        self
+    }
+
+    /// mlock the memory, see `Bytes::lock`
+    pub fn lock(&mut self, lock: bool) -> &mut Self {
+        self.lock = lock;
+        self
+    }
+
+    /// numa placement applied before the memory is prefaulted, see `Bytes::set_numa_policy`
+    pub fn numa_policy(&mut self, policy: NumaPolicy) -> &mut Self {
+        self.numa_policy = Some(policy);
+        self
+    }
+
+    /// seal the size of a memfd with F_SEAL_GROW and F_SEAL_SHRINK
+    pub fn seal_size(&mut self, seal_size: bool) -> &mut Self {
+        self.seal_size = seal_size;
+        self
+    }
+
+    /// remove the backing file or shm segment when the `Bytes` is dropped
+    pub fn unlink_on_drop(&mut self, unlink_on_drop: bool) -> &mut Self {
+        self.unlink_on_drop = unlink_on_drop;
+        self
+    }
+
+    /// hold the owner lock of the file or shm segment, fails with AlreadyExists if another
+    /// live process owns it. see `segments::OwnerLock`
+    pub fn owner_lock(&mut self, owner_lock: bool) -> &mut Self {
+        self.owner_lock = owner_lock;
+        self
+    }
+
+    /// map an existing file or shm segment read only, requires `OpenMode::Open`
+    pub fn read_only(&mut self, read_only: bool) -> &mut Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn build(&self) -> Result<Bytes, BytesError> {
+        if !self.alignment.is_power_of_two() {
+            return Err(BytesError::InvalidAlignment(self.alignment));
+        }
+        if self.read_only && self.open_mode != OpenMode::Open {
+            return Err(BytesError::Unsupported("create read only"));
+        }
+        //taken before the segment is created so cleanup_stale never sees it unowned
+        let owner_lock = if self.owner_lock {
+            Some(self.acquire_owner_lock()?)
+        } else {
+            None
+        };
+        let (mut bytes, segment) = match &self.backing {
+            Backing::Heap => (self.build_heap()?, None),
+            Backing::File(path) => {
+                let (bytes, created) = self.build_file(path)?;
+                (bytes, Some((Segment::File(path.clone()), created)))
+            }
+            #[cfg(target_os = "linux")]
+            Backing::Shm(name) => {
+                let (bytes, created) = self.build_shm(name)?;
+                (bytes, Some((Segment::Shm(name.clone()), created)))
+            }
+            #[cfg(target_os = "linux")]
+            Backing::MemFd(name) => (self.build_memfd(name)?, None),
+        };
+        if let Err(err) = self.tune(&mut bytes) {
+            //do not leave a segment behind that we created but failed to set up
+            if let Some((segment, true)) = segment {
+                segment.unlink();
+            }
+            return Err(err);
+        }
+        if self.unlink_on_drop {
+            if let Some((segment, _)) = segment {
+                bytes.set_unlink_on_drop(Some(segment));
+            }
+        }
+        bytes.set_owner_lock(owner_lock);
+        Ok(bytes)
+    }
+
+    fn acquire_owner_lock(&self) -> Result<OwnerLock, BytesError> {
+        if self.read_only {
+            return Err(BytesError::Unsupported("owner lock read only"));
+        }
+        let segment = match &self.backing {
+            Backing::File(path) => path.clone(),
+            #[cfg(target_os = "linux")]
+            Backing::Shm(name) => crate::shm::path(name)?,
+            _ => return Err(BytesError::Unsupported("owner lock")),
+        };
+        OwnerLock::acquire(segment)
+    }
+
+    fn create_size(&self) -> Result<u64, BytesError> {
+        match self.size {
+            Some(size) if size > 0 && size <= isize::MAX as u64 => Ok(size),
+            Some(size) => Err(BytesError::InvalidSize(size)),
+            None => Err(BytesError::InvalidSize(0)),
+        }
+    }
+
+    fn check_mapping_alignment(&self) -> Result<(), BytesError> {
+        #[cfg(target_os = "linux")]
+        if self.alignment > crate::pages::page_size() {
+            return Err(BytesError::InvalidAlignment(self.alignment));
+        }
+        Ok(())
+    }
+
+    fn build_heap(&self) -> Result<Bytes, BytesError> {
+        if self.open_mode == OpenMode::Open {
+            return Err(BytesError::Unsupported("open heap"));
+        }
+        let size = self.create_size()? as usize;
+        #[cfg(target_os = "linux")]
+        if self.huge_pages {
+            self.check_mapping_alignment()?;
+            return Bytes::try_heap_allocate_huge(size);
+        }
+        Bytes::try_heap_allocate_aligned(size, self.alignment)
+    }
+
+    // returns the mapped file and whether it was created by this call
+    fn build_file(&self, path: &PathBuf) -> Result<(Bytes, bool), BytesError> {
+        self.check_mapping_alignment()?;
+        if self.read_only {
+            let file = File::open(path)?;
+            return Ok((Bytes::try_from_file_read_only(file, self.size)?, false));
+        }
+        let open = || OpenOptions::new().read(true).write(true).open(path);
+        let create = || -> Result<File, BytesError> {
+            let size = self.create_size()?;
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .mode(self.permissions)
+                .open(path)?;
+            file.set_len(size).inspect_err(|_| {
+                let _ = fs::remove_file(path);
+            })?;
+            Ok(file)
+        };
+        let (file, created) = self.open_or_create(open, create)?;
+        let mapped = Bytes::try_from_file(file, self.size);
+        if created && mapped.is_err() {
+            let _ = fs::remove_file(path);
+        }
+        Ok((mapped?, created))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn build_shm(&self, name: &str) -> Result<(Bytes, bool), BytesError> {
+        self.check_mapping_alignment()?;
+        if self.read_only {
+            let file = crate::shm::open_read_only(name)?;
+            return Ok((Bytes::try_from_file_read_only(file, self.size)?, false));
+        }
+        let open = || crate::shm::open(name, false, 0);
+        let create = || -> Result<File, BytesError> {
+            let size = self.create_size()?;
+            let file = crate::shm::open(name, true, self.permissions)?;
+            file.set_len(size).inspect_err(|_| {
+                let _ = crate::shm::unlink(name);
+            })?;
+            Ok(file)
+        };
+        let (file, created) = self.open_or_create(open, create)?;
+        let mapped = Bytes::try_from_file(file, self.size);
+        if created && mapped.is_err() {
+            let _ = crate::shm::unlink(name);
+        }
+        Ok((mapped?, created))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn build_memfd(&self, name: &str) -> Result<Bytes, BytesError> {
+        if self.open_mode == OpenMode::Open {
+            return Err(BytesError::Unsupported("open memfd"));
+        }
+        self.check_mapping_alignment()?;
+        Bytes::try_memfd_create(name, self.create_size()?, self.seal_size)
+    }
+
+    fn open_or_create<O, C>(&self, open: O, create: C) -> Result<(File, bool), BytesError>
+    where
+        O: Fn() -> io::Result<File>,
+        C: Fn() -> Result<File, BytesError>,
+    {
+        match self.open_mode {
+            OpenMode::Create => Ok((create()?, true)),
+            OpenMode::Open => Ok((open()?, false)),
+            OpenMode::CreateOrOpen => match create() {
+                Ok(file) => Ok((file, true)),
+                Err(BytesError::AlreadyExists) => Ok((open()?, false)),
+                Err(err) => Err(err),
+            },
+        }
+    }
+
+    // order matters, huge pages must be requested before the pages are faulted in
+    // and blocks reserved before they are touched
+    #[cfg(target_os = "linux")]
+    fn tune(&self, bytes: &mut Bytes) -> Result<(), BytesError> {
+        if let Some(policy) = &self.numa_policy {
+            bytes.set_numa_policy(policy)?;
+        }
+        if self.huge_pages && bytes.page_mode() == PageMode::Regular {
+            bytes.advise_huge_pages();
+        }
+        if self.preallocate {
+            bytes.preallocate()?;
+        }
+        if self.prefault {
+            bytes.warm()?;
+        }
+        if self.lock {
+            bytes.lock()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn tune(&self, _bytes: &mut Bytes) -> Result<(), BytesError> {
+        if self.huge_pages
+            || self.preallocate
+            || self.prefault
+            || self.lock
+            || self.numa_policy.is_some()
+        {
+            return Err(BytesError::Unsupported("tuning"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::{
+        Bytes, BytesAtomicView, BytesError, LoadStore, NumaPolicy, CACHE_LINE_SIZE,
+    };
+    use crate::options::{Backing, BytesOptions, OpenMode};
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+    use std::sync::atomic::Ordering::{Acquire, Release};
+
+    fn shm_path(name: &str) -> PathBuf {
+        PathBuf::from(format!(
+            "/dev/shm/crossbytes.test.options.{}.{}",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_heap_options() {
+        let bytes = BytesOptions::new()
+            .size(4096)
+            .alignment(4096)
+            .prefault(true)
+            .lock(true)
+            .numa_policy(NumaPolicy::Preferred(0))
+            .build()
+            .unwrap();
+        assert_eq!(4096, bytes.capacity());
+        assert_eq!(4096, bytes.alignment());
+        assert!(bytes.is_locked());
+    }
+
+    #[test]
+    fn test_heap_requires_size() {
+        let res = BytesOptions::new().build();
+        assert!(matches!(res, Err(BytesError::InvalidSize(0))));
+        let res = BytesOptions::new().size(64).alignment(3).build();
+        assert!(matches!(res, Err(BytesError::InvalidAlignment(3))));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_file_create_or_open() {
+        let path = shm_path("create_or_open");
+        let _ = fs::remove_file(&path);
+        let mut options = BytesOptions::new();
+        options
+            .backing(Backing::File(path.clone()))
+            .open_mode(OpenMode::CreateOrOpen)
+            .size(4096)
+            .permissions(0o600)
+            .preallocate(true)
+            .prefault(true);
+        let created = options.build().unwrap();
+        let opened = options.build().unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+
+        let mut view = BytesAtomicView::from_bytes(0, 4096, &created);
+        view.store_at(0, 3u64, Release);
+        let opened_view = BytesAtomicView::from_bytes(0, 4096, &opened);
+        assert_eq!(3u64, opened_view.load_at(0, Acquire));
+
+        let res = options.open_mode(OpenMode::Create).build();
+        assert!(matches!(res, Err(BytesError::AlreadyExists)));
+        let res = options.open_mode(OpenMode::Open).size(8192).build();
+        assert!(matches!(res, Err(BytesError::SizeMismatch { .. })));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_read_only_options() {
+        let name = format!("crossbytes.test.options.ro.{}", std::process::id());
+        let mut options = BytesOptions::new();
+        options.backing(Backing::Shm(name.clone())).size(4096);
+        let created = options.unlink_on_drop(true).build().unwrap();
+        let res = options.unlink_on_drop(false).read_only(true).build();
+        assert!(matches!(res, Err(BytesError::Unsupported(_))));
+        let read_only = options.open_mode(OpenMode::Open).build().unwrap();
+        assert!(read_only.is_read_only());
+        assert!(!created.is_read_only());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_unlink_on_drop() {
+        let path = shm_path("unlink");
+        let _ = fs::remove_file(&path);
+        let bytes = BytesOptions::new()
+            .backing(Backing::File(path.clone()))
+            .size(4096)
+            .unlink_on_drop(true)
+            .build()
+            .unwrap();
+        assert!(path.exists());
+        drop(bytes);
+        assert!(!path.exists());
+
+        let name = format!("crossbytes.test.options.shm.{}", std::process::id());
+        let bytes = BytesOptions::new()
+            .backing(Backing::Shm(name.clone()))
+            .size(4096)
+            .unlink_on_drop(true)
+            .build()
+            .unwrap();
+        assert!(Bytes::try_shm_open(&name, None).is_ok());
+        drop(bytes);
+        assert!(matches!(
+            Bytes::try_shm_open(&name, None),
+            Err(BytesError::NotFound)
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_owner_lock() {
+        let name = format!("crossbytes.test.options.owner.{}", std::process::id());
+        let mut options = BytesOptions::new();
+        options
+            .backing(Backing::Shm(name.clone()))
+            .open_mode(OpenMode::CreateOrOpen)
+            .size(4096)
+            .owner_lock(true)
+            .unlink_on_drop(true);
+        let owner = options.build().unwrap();
+        assert!(owner.is_owner());
+        assert!(matches!(options.build(), Err(BytesError::AlreadyExists)));
+        drop(owner);
+        let next_owner = options.build().unwrap();
+        assert!(next_owner.is_owner());
+
+        let res = BytesOptions::new().size(64).owner_lock(true).build();
+        assert!(matches!(res, Err(BytesError::Unsupported(_))));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_memfd_options() {
+        let bytes = BytesOptions::new()
+            .backing(Backing::MemFd("crossbytes.test".into()))
+            .size(4096)
+            .seal_size(true)
+            .huge_pages(true)
+            .build()
+            .unwrap();
+        assert!(crate::memfd::is_size_sealed(bytes.fd().unwrap()).unwrap());
+        assert_eq!(CACHE_LINE_SIZE.max(4096), bytes.alignment());
+
+        let res = BytesOptions::new()
+            .backing(Backing::MemFd("crossbytes.test".into()))
+            .open_mode(OpenMode::Open)
+            .build();
+        assert!(matches!(res, Err(BytesError::Unsupported(_))));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::bytes::BytesAtomicView;
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of, MaybeUninit};
+use std::sync::atomic::{
+    AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32, AtomicU64,
+    AtomicU8, AtomicUsize,
+};
+
+#[cfg(feature = "derive")]
+pub use crossbytes_derive::Overlay;
+
+/// largest alignment an overlay may have, every `Bytes` is at least 8 byte aligned
+pub const MAX_OVERLAY_ALIGN: usize = 8;
+
+/// plain old data which can be copied in and out of shared memory as bytes
+/// # Safety
+/// the type must have no padding, no drop glue and every bit pattern must be a valid value
+pub unsafe trait Pod: Sized + 'static {}
+
+/// atomic types, fields of these types are accessed in place rather than copied
+/// # Safety
+/// the type must be one of the std atomics, all access through a shared reference is atomic
+pub unsafe trait AtomicField: Pod + Sync {}
+
+/// `#[repr(C)]` struct which can be laid over a region of a view, see `BytesAtomicView::overlay`.
+/// implement it with `#[derive(Overlay)]` which checks the layout at compile time
+/// # Safety
+/// the type must be `#[repr(C)]`, have no padding and be aligned to at most `MAX_OVERLAY_ALIGN`
+pub unsafe trait Overlay: Pod {
+    /// typed accessor with a method per field
+    type Accessor<'a>;
+
+    fn accessor(view: OverlayView<'_, Self>) -> Self::Accessor<'_>;
+}
+
+macro_rules! pod_impl {
+    ($($type: ty),*) => {
+        $(unsafe impl Pod for $type {})*
+    };
+}
+
+macro_rules! atomic_field_impl {
+    ($($type: ty),*) => {
+        $(
+            unsafe impl Pod for $type {}
+            unsafe impl AtomicField for $type {}
+        )*
+    };
+}
+
+pod_impl!(u8, u16, u32, u64, i8, i16, i32, i64, usize, isize, f32, f64);
+atomic_field_impl!(
+    AtomicU8,
+    AtomicU16,
+    AtomicU32,
+    AtomicU64,
+    AtomicI8,
+    AtomicI16,
+    AtomicI32,
+    AtomicI64,
+    AtomicUsize,
+    AtomicIsize
+);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// region of a view holding a T, aligned for T. used by the accessors `#[derive(Overlay)]`
+/// generates, non atomic fields are copied in and out with relaxed byte accesses
+pub struct OverlayView<'a, T> {
+    view: BytesAtomicView<'a>,
+    _type: PhantomData<T>,
+}
+
+impl<'a, T: Pod> OverlayView<'a, T> {
+    // view must be exactly the size of T and aligned for T
+    pub(crate) fn new(view: BytesAtomicView<'a>) -> OverlayView<'a, T> {
+        debug_assert_eq!(size_of::<T>(), view.len());
+        debug_assert_eq!(0, view.as_ptr() as usize % align_of::<T>());
+        OverlayView {
+            view,
+            _type: PhantomData,
+        }
+    }
+
+    /// copy of the whole struct, fields written concurrently may be torn
+    pub fn load(&self) -> T {
+        self.read(0)
+    }
+
+    /// overwrite the whole struct
+    pub fn store(&mut self, val: T) {
+        self.write(0, val)
+    }
+
+    /// copy of the field F at offset
+    pub fn read<F: Pod>(&self, offset: usize) -> F {
+        let mut val = MaybeUninit::<F>::zeroed();
+        let bytes =
+            unsafe { std::slice::from_raw_parts_mut(val.as_mut_ptr() as *mut u8, size_of::<F>()) };
+        self.view.copy_to_slice(offset, bytes);
+        //any bit pattern is a valid F
+        unsafe { val.assume_init() }
+    }
+
+    /// overwrite the field F at offset
+    pub fn write<F: Pod>(&mut self, offset: usize, val: F) {
+        //F has no padding, all of its bytes are initialised
+        let bytes =
+            unsafe { std::slice::from_raw_parts(&val as *const F as *const u8, size_of::<F>()) };
+        self.view.copy_from_slice(offset, bytes);
+    }
+
+    /// the atomic field at offset, accessed in place
+    pub fn atomic<A: AtomicField>(&self, offset: usize) -> &'a A {
+        let bytes = &self.view.as_atomic_bytes()[offset..offset + size_of::<A>()];
+        let ptr = bytes.as_ptr() as *const A;
+        assert!(
+            ptr.is_aligned(),
+            "misaligned atomic field at offset={}",
+            offset
+        );
+        unsafe { &*ptr }
+    }
+
+    pub fn view(&self) -> &BytesAtomicView<'a> {
+        &self.view
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::{AccessError, Bytes, BytesAtomicView, LoadStore};
+    use crate::overlay::OverlayView;
+    use crossbytes_derive::Overlay;
+    use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+    use std::sync::atomic::{AtomicU32, AtomicU64};
+
+    #[derive(Overlay)]
+    #[repr(C)]
+    struct Quote {
+        sequence: AtomicU64,
+        price: f64,
+        size: i32,
+        flags: AtomicU32,
+        symbol: [u8; 8],
+    }
+
+    #[derive(Overlay, Debug, Copy, Clone, PartialEq)]
+    #[repr(C, align(8))]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[test]
+    fn test_overlay_fields() {
+        let bytes = Bytes::heap_allocate(128);
+        let view = BytesAtomicView::from_bytes(0, 128, &bytes);
+        let mut quote = view.overlay::<Quote>(32);
+        quote.set_price(101.5);
+        quote.set_size(-7);
+        quote.set_symbol(*b"EURUSD\0\0");
+        quote.sequence().store(3, Release);
+        quote.flags().fetch_or(0b10, Relaxed);
+        assert_eq!(3u64, view.load_at(32, Acquire));
+        assert_eq!(101.5, view.get_f64_ne(40));
+        assert_eq!(-7, view.get_i32_ne(48));
+        assert_eq!(2u32, view.load_at(52, Relaxed));
+        assert_eq!(101.5, quote.price());
+        assert_eq!(-7, quote.size());
+        assert_eq!(*b"EURUSD\0\0", quote.symbol());
+
+        let copy = quote.load();
+        assert_eq!(3, copy.sequence.load(Relaxed));
+        assert_eq!(*b"EURUSD\0\0", copy.symbol);
+        let other = view.overlay::<Quote>(96);
+        assert_eq!(0, other.sequence().load(Relaxed));
+    }
+
+    #[test]
+    fn test_overlay_copy_in_out() {
+        let bytes = Bytes::heap_allocate(64);
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        let mut point = view.overlay::<Point>(8);
+        point.store(Point { x: 1, y: -2 });
+        assert_eq!(Point { x: 1, y: -2 }, point.load());
+        assert_eq!(-2, point.y());
+        let points: OverlayView<'_, [Point; 2]> = OverlayView::new(view.sub_view(16, 16));
+        assert_eq!([Point { x: 0, y: 0 }; 2], points.load());
+    }
+
+    #[test]
+    fn test_overlay_bounds_and_alignment() {
+        let bytes = Bytes::heap_allocate(64);
+        let view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        assert_eq!(
+            Err(AccessError::Misaligned {
+                offset: 4,
+                align: 8
+            }),
+            view.try_overlay::<Quote>(4).map(|_| ())
+        );
+        assert_eq!(
+            Err(AccessError::OutOfBounds {
+                offset: 40,
+                size: 32,
+                length: 64
+            }),
+            view.try_overlay::<Quote>(40).map(|_| ())
+        );
+        assert!(view.try_overlay::<Quote>(32).is_ok());
+        assert_eq!(32, std::mem::size_of::<Quote>());
+    }
+}
@@ -0,0 +1,268 @@
+use std::fs::{self, File};
+use std::io;
+use std::os::fd::{AsRawFd, BorrowedFd};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const DEFAULT_HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+const HUGETLBFS_MAGIC: libc::c_long = 0x958458f6;
+const THP_ENABLED: &str = "/sys/kernel/mm/transparent_hugepage/enabled";
+const THP_SHMEM_ENABLED: &str = "/sys/kernel/mm/transparent_hugepage/shmem_enabled";
+const NODE_DIR: &str = "/sys/devices/system/node";
+// move pages already faulted in which are only mapped by this process
+const MPOL_MF_MOVE: libc::c_uint = 1 << 1;
+
+pub(crate) fn page_size() -> usize {
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size <= 0 {
+        4096
+    } else {
+        size as usize
+    }
+}
+
+/// default huge page size of the system as reported by /proc/meminfo
+pub(crate) fn huge_page_size() -> usize {
+    fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| {
+            meminfo
+                .lines()
+                .find(|line| line.starts_with("Hugepagesize:"))
+                .and_then(|line| line.split_whitespace().nth(1))
+                .and_then(|kb| kb.parse::<usize>().ok())
+        })
+        .map(|kb| kb * 1024)
+        .unwrap_or(DEFAULT_HUGE_PAGE_SIZE)
+}
+
+/// true if file lives on a hugetlbfs mount, mappings of such files always use huge pages
+pub(crate) fn is_hugetlbfs(file: &File) -> bool {
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    let res = unsafe { libc::fstatfs(file.as_raw_fd(), &mut stat) };
+    res == 0 && stat.f_type as libc::c_long == HUGETLBFS_MAGIC
+}
+
+// the selected mode is shown in brackets eg "always [madvise] never"
+fn transparent_huge_pages_enabled(shmem: bool) -> bool {
+    let setting = if shmem {
+        THP_SHMEM_ENABLED
+    } else {
+        THP_ENABLED
+    };
+    match fs::read_to_string(setting) {
+        Ok(modes) => !(modes.contains("[never]") || modes.contains("[deny]")),
+        Err(_) => false,
+    }
+}
+
+/// madvise(MADV_HUGEPAGE) the page aligned part of the region,
+/// returns true if transparent huge pages are now in effect for it
+pub(crate) fn advise_huge_pages(ptr: *mut u8, len: usize, shmem: bool) -> io::Result<bool> {
+    let page_size = page_size();
+    let start = ptr.align_offset(page_size);
+    if start >= len {
+        return Ok(false);
+    }
+    let aligned_len = (len - start) / page_size * page_size;
+    if aligned_len == 0 {
+        return Ok(false);
+    }
+    let res = unsafe {
+        libc::madvise(
+            ptr.add(start) as *mut libc::c_void,
+            aligned_len,
+            libc::MADV_HUGEPAGE,
+        )
+    };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(transparent_huge_pages_enabled(shmem))
+}
+
+// expands the region to whole pages, required by madvise and mlock
+fn page_range(ptr: *mut u8, len: usize) -> (*mut libc::c_void, usize) {
+    let page_size = page_size();
+    let addr = ptr as usize;
+    let start = addr & !(page_size - 1);
+    let end = (addr + len).next_multiple_of(page_size);
+    (start as *mut libc::c_void, end - start)
+}
+
+/// fallocate len bytes of the file so the blocks are reserved up front
+pub(crate) fn preallocate(fd: BorrowedFd<'_>, len: usize) -> io::Result<()> {
+    let res = unsafe { libc::fallocate(fd.as_raw_fd(), 0, 0, len as libc::off_t) };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// populate the page tables for the region, writable if write is set, falling back to
+/// touching each page on kernels without MADV_POPULATE_(READ|WRITE) (5.14)
+pub(crate) fn prefault(ptr: *mut u8, len: usize, write: bool) -> io::Result<()> {
+    let (start, aligned_len) = page_range(ptr, len);
+    let advice = if write {
+        libc::MADV_POPULATE_WRITE
+    } else {
+        libc::MADV_POPULATE_READ
+    };
+    let res = unsafe { libc::madvise(start, aligned_len, advice) };
+    if res == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() != Some(libc::EINVAL) {
+        return Err(err);
+    }
+    let page_size = page_size();
+    let mut offset = 0;
+    while offset < len {
+        let byte = unsafe { AtomicU8::from_ptr(ptr.add(offset)) };
+        if write {
+            //an atomic add of zero write faults the page without changing its contents,
+            //even if another thread or process is writing to it concurrently
+            byte.fetch_add(0, Ordering::Relaxed);
+        } else {
+            byte.load(Ordering::Relaxed);
+        }
+        offset += page_size - (ptr as usize + offset) % page_size;
+    }
+    Ok(())
+}
+
+pub(crate) fn lock(ptr: *mut u8, len: usize) -> io::Result<()> {
+    let (start, aligned_len) = page_range(ptr, len);
+    if unsafe { libc::mlock(start, aligned_len) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub(crate) fn unlock(ptr: *mut u8, len: usize) -> io::Result<()> {
+    let (start, aligned_len) = page_range(ptr, len);
+    if unsafe { libc::munlock(start, aligned_len) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// number of numa nodes with memory, 1 on machines or kernels without numa support
+pub(crate) fn numa_node_count() -> usize {
+    let count = fs::read_dir(NODE_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    name.strip_prefix("node")
+                        .is_some_and(|id| id.parse::<u32>().is_ok())
+                })
+                .count()
+        })
+        .unwrap_or(0);
+    count.max(1)
+}
+
+/// mbind the region with mode (MPOL_*) over nodes, pages already faulted in are moved
+pub(crate) fn bind(ptr: *mut u8, len: usize, mode: libc::c_int, nodes: &[u32]) -> io::Result<()> {
+    let bits = libc::c_ulong::BITS as usize;
+    let max_node = nodes.iter().max().map_or(0, |node| *node as usize);
+    let mut mask = vec![0 as libc::c_ulong; max_node / bits + 1];
+    for node in nodes {
+        let node = *node as usize;
+        mask[node / bits] |= 1 << (node % bits);
+    }
+    let (start, aligned_len) = page_range(ptr, len);
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            start,
+            aligned_len,
+            mode,
+            mask.as_ptr(),
+            //the kernel reads one bit less than maxnode
+            (mask.len() * bits + 1) as libc::c_ulong,
+            MPOL_MF_MOVE,
+        )
+    };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// node of each page of the region using move_pages in query mode,
+/// None for pages which have not been faulted in yet
+pub(crate) fn page_nodes(ptr: *mut u8, len: usize) -> io::Result<Vec<Option<u32>>> {
+    let page_size = page_size();
+    let (start, aligned_len) = page_range(ptr, len);
+    let pages: Vec<*mut libc::c_void> = (0..aligned_len / page_size)
+        .map(|page| unsafe { (start as *mut u8).add(page * page_size) } as *mut libc::c_void)
+        .collect();
+    let mut status = vec![0 as libc::c_int; pages.len()];
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_move_pages,
+            0,
+            pages.len() as libc::c_ulong,
+            pages.as_ptr(),
+            std::ptr::null::<libc::c_int>(),
+            status.as_mut_ptr(),
+            0,
+        )
+    };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    //negative status is an errno, eg ENOENT for a page which is not present
+    Ok(status
+        .into_iter()
+        .map(|node| u32::try_from(node).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pages::{
+        bind, huge_page_size, is_hugetlbfs, numa_node_count, page_nodes, page_size, prefault,
+    };
+    use memmap2::MmapOptions;
+    use std::fs;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_page_sizes() {
+        assert!(page_size().is_power_of_two());
+        assert!(huge_page_size().is_power_of_two());
+        assert!(huge_page_size() > page_size());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_numa_node_count() {
+        assert!(numa_node_count() >= 1);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_bind_and_query_nodes() {
+        let len = 4 * page_size();
+        let mut mmap = MmapOptions::new().len(len).map_anon().unwrap();
+        let ptr = mmap.as_mut_ptr();
+        bind(ptr, len, libc::MPOL_BIND, &[0]).unwrap();
+        assert_eq!(vec![None; 4], page_nodes(ptr, len).unwrap());
+        prefault(ptr, len, true).unwrap();
+        assert_eq!(vec![Some(0); 4], page_nodes(ptr, len).unwrap());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_tmpfs_is_not_hugetlbfs() {
+        let path = format!("/dev/shm/crossbytes.test.pages.{}", std::process::id());
+        let file = fs::File::create(&path).unwrap();
+        assert!(!is_hugetlbfs(&file));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,270 @@
+#[cfg(target_os = "linux")]
+use crate::bytes::Bytes;
+use crate::bytes::BytesError;
+use crate::header::process_start_time;
+#[cfg(target_os = "linux")]
+use crate::header::SegmentHeader;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// suffix of the lock file kept next to an owned segment
+pub const OWNER_SUFFIX: &str = ".owner";
+
+/// path of the owner lock file of segment, eg /dev/shm/broadcast.owner
+pub fn owner_lock_path(segment: &Path) -> PathBuf {
+    let mut path = segment.as_os_str().to_owned();
+    path.push(OWNER_SUFFIX);
+    PathBuf::from(path)
+}
+
+// returns false if the lock is held by someone else
+fn try_flock(file: &File, operation: libc::c_int) -> io::Result<bool> {
+    let res = unsafe { libc::flock(file.as_raw_fd(), operation | libc::LOCK_NB) };
+    if res == 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+/// exclusive flock on the owner lock file of a segment, held by the creator for as long as
+/// it owns the segment. the lock file records the pid and start time of the owner.
+/// the kernel drops the flock when the owner dies, which is how `cleanup_stale`
+/// tells a live owner from a dead one
+#[derive(Debug)]
+pub struct OwnerLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl OwnerLock {
+    /// lock the owner file of segment, fails with AlreadyExists if a live owner holds it
+    pub fn acquire<P: AsRef<Path>>(segment: P) -> Result<OwnerLock, BytesError> {
+        let path = owner_lock_path(segment.as_ref());
+        loop {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)?;
+            if !try_flock(&file, libc::LOCK_EX)? {
+                return Err(BytesError::AlreadyExists);
+            }
+            //the file may have been removed by cleanup_stale between open and flock
+            let locked = file.metadata()?;
+            match fs::metadata(&path) {
+                Ok(current) if current.dev() == locked.dev() && current.ino() == locked.ino() => {}
+                Ok(_) => continue,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            }
+            let pid = std::process::id();
+            let start_time = process_start_time(pid).unwrap_or(0);
+            file.set_len(0)?;
+            (&file).write_all(format!("{} {}\n", pid, start_time).as_bytes())?;
+            return Ok(OwnerLock { _file: file, path });
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for OwnerLock {
+    fn drop(&mut self) {
+        //removed while still locked, the flock is released when the file is closed
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// segment the owner lock file at path belongs to, None if it is not a lock file
+fn owned_segment(path: &Path) -> Option<PathBuf> {
+    let segment = path.to_str()?.strip_suffix(OWNER_SUFFIX)?;
+    Some(PathBuf::from(segment))
+}
+
+// pid and start time recorded in an owner lock file as "<pid> <start_time>\n"
+fn parse_owner(content: &str) -> Option<(u32, u64)> {
+    let (pid, start_time) = content.strip_suffix('\n')?.split_once(' ')?;
+    Some((pid.parse().ok()?, start_time.parse().ok()?))
+}
+
+// true if the process with pid is running and is the one started at start_time
+#[cfg(target_os = "linux")]
+fn is_alive(pid: u32, start_time: u64) -> bool {
+    match process_start_time(pid) {
+        //start time could not be read by the creator, only the pid can be checked
+        Some(_) if start_time == 0 => true,
+        Some(current) => current == start_time,
+        None => false,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_segment(path: &Path) -> Option<Bytes> {
+    if !fs::metadata(path).is_ok_and(|meta| meta.is_file()) {
+        return None;
+    }
+    Bytes::try_open_file_backed_read_only(path, None).ok()
+}
+
+#[cfg(target_os = "linux")]
+fn has_stale_header(path: &Path) -> bool {
+    let Some(bytes) = open_segment(path) else {
+        return false;
+    };
+    match SegmentHeader::read(&bytes) {
+        Ok(header) => !is_alive(header.pid, header.start_time),
+        Err(_) => false,
+    }
+}
+
+/// remove the segments in dir whose owning process is gone, returns the removed segments.
+/// a segment with an owner lock file is stale once nothing holds the lock and the owner
+/// recorded in it has exited, a segment with a `SegmentHeader` and no lock file once the
+/// process which created it has exited. other files are left alone, processes which still
+/// have a removed segment mapped can continue to use it
+#[cfg(target_os = "linux")]
+pub fn cleanup_stale<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>, BytesError> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    let mut removed = Vec::new();
+    for lock_path in &paths {
+        let Some(segment) = owned_segment(lock_path) else {
+            continue;
+        };
+        let Ok(file) = File::open(lock_path) else {
+            continue;
+        };
+        if !try_flock(&file, libc::LOCK_EX).unwrap_or(false) {
+            continue;
+        }
+        //only remove what this crate created, a lock file left by someone else is kept
+        let mut content = String::new();
+        let owner = match (&file).read_to_string(&mut content) {
+            Ok(_) => parse_owner(&content),
+            Err(_) => None,
+        };
+        if owner.is_none_or(|(pid, start_time)| is_alive(pid, start_time)) {
+            continue;
+        }
+        //while the lock is held no new owner can take over the segment
+        if fs::remove_file(&segment).is_ok() {
+            removed.push(segment);
+        }
+        let _ = fs::remove_file(lock_path);
+    }
+    for segment in paths.iter().filter(|path| owned_segment(path).is_none()) {
+        if owner_lock_path(segment).exists() || removed.contains(segment) {
+            continue;
+        }
+        if has_stale_header(segment) && fs::remove_file(segment).is_ok() {
+            removed.push(segment.clone());
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::{Bytes, BytesAtomicView, BytesError, LoadStore};
+    use crate::header::{RegionKind, SegmentHeader};
+    #[cfg(target_os = "linux")]
+    use crate::segments::cleanup_stale;
+    use crate::segments::{owner_lock_path, OwnerLock};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::Ordering::Relaxed;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = PathBuf::from(format!(
+            "/dev/shm/crossbytes.test.segments.{}.{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_owner_lock_is_exclusive() {
+        let dir = test_dir("lock");
+        let segment = dir.join("broadcast");
+        let lock = OwnerLock::acquire(&segment).unwrap();
+        assert_eq!(owner_lock_path(&segment), lock.path());
+        let content = fs::read_to_string(lock.path()).unwrap();
+        assert!(content.starts_with(&format!("{} ", std::process::id())));
+        assert!(matches!(
+            OwnerLock::acquire(&segment),
+            Err(BytesError::AlreadyExists)
+        ));
+        drop(lock);
+        assert!(!owner_lock_path(&segment).exists());
+        assert!(OwnerLock::acquire(&segment).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(target_os = "linux")]
+    fn test_cleanup_stale() {
+        let dir = test_dir("cleanup");
+        //owned by a live lock
+        let live = dir.join("live");
+        let _lock = OwnerLock::acquire(&live).unwrap();
+        fs::write(&live, [0u8; 64]).unwrap();
+        //lock file left behind by an owner which died, pid is above the kernel limit
+        let orphan = dir.join("orphan");
+        let orphan_bytes = Bytes::from_file_backed(&orphan, 128);
+        SegmentHeader::init(&orphan_bytes, RegionKind::Raw).unwrap();
+        fs::write(owner_lock_path(&orphan), "4194305 1\n").unwrap();
+        //lock file which was not written by an OwnerLock
+        let foreign = dir.join("foreign");
+        let foreign_bytes = Bytes::from_file_backed(&foreign, 128);
+        SegmentHeader::init(&foreign_bytes, RegionKind::Raw).unwrap();
+        fs::write(owner_lock_path(&foreign), "junk").unwrap();
+        //create_owned of an owner killed before dropping it, there is no header
+        let killed = dir.join("killed");
+        fs::write(&killed, [0u8; 32]).unwrap();
+        fs::write(owner_lock_path(&killed), "4194305 1\n").unwrap();
+        //header written by a previous process with the same pid
+        let stale = dir.join("stale");
+        let bytes = Bytes::from_file_backed(&stale, 128);
+        SegmentHeader::init(&bytes, RegionKind::Raw).unwrap();
+        SegmentHeader::mark_ready(&bytes);
+        let mut view = BytesAtomicView::from_bytes(0, 64, &bytes);
+        view.store_at(32, 1u64, Relaxed);
+        //header of this process
+        let current = dir.join("current");
+        let current_bytes = Bytes::from_file_backed(&current, 128);
+        SegmentHeader::init(&current_bytes, RegionKind::Raw).unwrap();
+        SegmentHeader::mark_ready(&current_bytes);
+        //not a segment
+        let other = dir.join("other");
+        fs::write(&other, "hello").unwrap();
+
+        let removed = cleanup_stale(&dir).unwrap();
+        assert_eq!(vec![killed.clone(), orphan.clone(), stale.clone()], removed);
+        assert!(!orphan.exists() && !owner_lock_path(&orphan).exists());
+        assert!(!stale.exists());
+        assert!(live.exists() && current.exists() && other.exists());
+        assert!(foreign.exists() && owner_lock_path(&foreign).exists());
+        assert!(!killed.exists() && !owner_lock_path(&killed).exists());
+        assert!(Bytes::try_create_owned(&killed, 32).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+use std::os::fd::FromRawFd;
+use std::path::PathBuf;
+
+const SHM_DIR: &str = "/dev/shm";
+
+/// path of the named segment on the file system,
+/// this is the path a jvm process would map with Agrona for the same name
+pub fn path(name: &str) -> io::Result<PathBuf> {
+    let name = segment_name(name)?;
+    Ok(PathBuf::from(SHM_DIR).join(name))
+}
+
+// names may be given with or without the leading '/' shm_open requires
+fn segment_name(name: &str) -> io::Result<&str> {
+    let name = name.strip_prefix('/').unwrap_or(name);
+    if name.is_empty() || name.contains('/') || name.contains('\0') || name == "." || name == ".." {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid shm segment name",
+        ));
+    }
+    Ok(name)
+}
+
+fn c_name(name: &str) -> io::Result<CString> {
+    let name = segment_name(name)?;
+    CString::new(format!("/{}", name))
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "name contains nul byte"))
+}
+
+/// open the named segment read + write, if create is set the segment must not already exist
+pub(crate) fn open(name: &str, create: bool, mode: u32) -> io::Result<File> {
+    let c_name = c_name(name)?;
+    let mut flags = libc::O_RDWR | libc::O_CLOEXEC;
+    if create {
+        flags |= libc::O_CREAT | libc::O_EXCL;
+    }
+    let fd = unsafe { libc::shm_open(c_name.as_ptr(), flags, mode as libc::mode_t) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+pub(crate) fn open_read_only(name: &str) -> io::Result<File> {
+    let c_name = c_name(name)?;
+    let fd = unsafe { libc::shm_open(c_name.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+/// remove the named segment, existing mappings stay valid until they are dropped
+pub(crate) fn unlink(name: &str) -> io::Result<()> {
+    let c_name = c_name(name)?;
+    let res = unsafe { libc::shm_unlink(c_name.as_ptr()) };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shm::{path, segment_name};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_segment_path() {
+        assert_eq!(
+            PathBuf::from("/dev/shm/broadcast"),
+            path("broadcast").unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("/dev/shm/broadcast"),
+            path("/broadcast").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_segment_names() {
+        assert!(segment_name("").is_err());
+        assert!(segment_name("/").is_err());
+        assert!(segment_name("a/b").is_err());
+        assert!(segment_name("..").is_err());
+    }
+}